@@ -6,7 +6,7 @@ use std::path::Path;
 
 use clap::{arg, Command};
 
-use textractor::extraction::extract;
+use textractor::extraction::{extract, extract_with_password};
 
 pub mod consts {
     pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -20,12 +20,21 @@ fn build_parser() -> Command {
         .author("Nathan LeRoy")
         .about("A command line tool that extracts text from files.")
         .arg(arg!(<path> "Path to file").required(false))
+        .arg(arg!(-p --password <PASSWORD> "Password for encrypted PDF files").required(false))
+}
+
+fn extract_text(data: &[u8], password: Option<&String>) -> Result<Option<String>> {
+    match password {
+        Some(password) => extract_with_password(data, password),
+        None => extract(data),
+    }
 }
 
 fn main() -> Result<()> {
     // parse the cli
     let app = build_parser();
     let matches = app.get_matches();
+    let password = matches.get_one::<String>("password");
 
     // build handler for stdout
     let stdout = stdout();
@@ -44,7 +53,7 @@ fn main() -> Result<()> {
 
         reader.read_to_end(&mut data)?;
 
-        let text = extract(&data)?;
+        let text = extract_text(&data, password)?;
 
         match text {
             Some(text) => {
@@ -59,7 +68,7 @@ fn main() -> Result<()> {
         let mut data = Vec::new();
         io::stdin().read_to_end(&mut data)?;
 
-        let text = extract(&data)?;
+        let text = extract_text(&data, password)?;
 
         match text {
             Some(text) => {
@@ -1,4 +1,7 @@
+use std::io::{Cursor, Read};
+
 use infer;
+use xml::reader::{EventReader, XmlEvent};
 
 #[derive(Debug, PartialEq)]
 pub enum ContentType {
@@ -27,11 +30,24 @@ pub enum ContentType {
     Epub,
     Mobi,
     Html,
+    Xml,
+    Json,
+    ICalendar,
+    VCard,
+    Hwp,
+    Hwpx,
+    Mhtml,
+    WebArchive,
+    Chm,
     Unknown,
 }
 
 impl From<&[u8]> for ContentType {
     fn from(value: &[u8]) -> Self {
+        if let Some(content_type) = sniff_container(value) {
+            return content_type;
+        }
+
         let file_type = infer::get(value);
         if let Some(file_type) = file_type {
             match file_type.mime_type() {
@@ -94,12 +110,134 @@ impl From<&[u8]> for ContentType {
                 "application/epub+zip" => ContentType::Epub,
                 "application/x-mobipocket-ebook" => ContentType::Mobi,
                 "text/html" => ContentType::Html,
+                "text/xml" => ContentType::Xml,
                 _ => ContentType::Unknown,
             }
         } else {
-            ContentType::Unknown
+            sniff_text(value)
+        }
+    }
+}
+
+///
+/// Detection for container formats (OLE2 compound files, zip packages, CHM, plists and MIME
+/// multipart messages) that `infer` would otherwise report as unknown or as something else.
+fn sniff_container(data: &[u8]) -> Option<ContentType> {
+    const OLE2_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+    if data.starts_with(OLE2_MAGIC) {
+        let mut file = cfb::CompoundFile::open(Cursor::new(data)).ok()?;
+        let mut signature = [0u8; 17];
+        file.open_stream("/FileHeader")
+            .ok()?
+            .read_exact(&mut signature)
+            .ok()?;
+        return (&signature == b"HWP Document File").then_some(ContentType::Hwp);
+    }
+
+    // zip packages that follow the OCF convention start with an uncompressed `mimetype` entry
+    if data.starts_with(b"PK\x03\x04") && data.get(30..38) == Some(b"mimetype") {
+        let name_len = u16::from_le_bytes([data[26], data[27]]) as usize;
+        let extra_len = u16::from_le_bytes([data[28], data[29]]) as usize;
+        let start = 30 + name_len + extra_len;
+        let mimetype = data.get(start..(start + 64).min(data.len()))?;
+        if mimetype.starts_with(b"application/hwp+zip") {
+            return Some(ContentType::Hwpx);
+        }
+    }
+
+    // the signature alone is plain text, so also check the version 3 header length
+    if data.starts_with(b"ITSF")
+        && data.get(4..8) == Some(&3u32.to_le_bytes())
+        && data.get(8..12) == Some(&0x60u32.to_le_bytes())
+    {
+        return Some(ContentType::Chm);
+    }
+
+    if (data.starts_with(b"bplist00") || find(&data[..data.len().min(512)], b"<plist").is_some())
+        && find(data, b"WebMainResource").is_some()
+    {
+        return Some(ContentType::WebArchive);
+    }
+
+    if is_mhtml(data) {
+        return Some(ContentType::Mhtml);
+    }
+
+    None
+}
+
+/// MHTML files are MIME messages whose top level headers declare a `multipart/related` body.
+fn is_mhtml(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(8192)];
+    let end = find(head, b"\r\n\r\n")
+        .or_else(|| find(head, b"\n\n"))
+        .unwrap_or(head.len());
+    let headers = String::from_utf8_lossy(&head[..end]).to_ascii_lowercase();
+
+    headers.starts_with(|c: char| c.is_ascii_alphabetic())
+        && headers.contains("mime-version:")
+        && headers.contains("multipart/related")
+}
+
+/// The position of the first occurrence of `needle` in `haystack`.
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+///
+/// Fallback detection for text based formats that `infer` does not know about.
+/// Anything that is valid UTF-8 is treated as text, with JSON and XML payloads
+/// singled out so they can go through a structured extractor.
+fn sniff_text(data: &[u8]) -> ContentType {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text.trim_start_matches('\u{feff}').trim_start(),
+        Err(_) => return ContentType::Unknown,
+    };
+
+    if text.contains('\0') {
+        return ContentType::Unknown;
+    }
+
+    let first_line = text.lines().next().unwrap_or("").trim();
+    if first_line.eq_ignore_ascii_case("BEGIN:VCALENDAR") {
+        return ContentType::ICalendar;
+    }
+    if first_line.eq_ignore_ascii_case("BEGIN:VCARD") {
+        return ContentType::VCard;
+    }
+
+    if (text.starts_with('{') || text.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        return ContentType::Json;
+    }
+
+    if text.starts_with('<') && looks_like_xml(text) {
+        return ContentType::Xml;
+    }
+
+    ContentType::Txt
+}
+
+/// An XML document without a declaration is accepted as long as its root element parses.
+fn looks_like_xml(text: &str) -> bool {
+    let reader = EventReader::new(text.as_bytes());
+    for event in reader {
+        match event {
+            Ok(XmlEvent::StartElement { name, .. }) => {
+                return !name.local_name.eq_ignore_ascii_case("html");
+            }
+            Ok(XmlEvent::StartDocument { .. })
+            | Ok(XmlEvent::Comment(_))
+            | Ok(XmlEvent::ProcessingInstruction { .. })
+            | Ok(XmlEvent::Whitespace(_)) => continue,
+            _ => return false,
         }
     }
+    false
 }
 
 // tests
@@ -124,4 +262,47 @@ mod tests {
         assert_eq!(content_type, ContentType::Txt);
     }
 
+    #[test]
+    fn test_detect_xml() {
+        let data: &[u8] = include_bytes!("../tests/data/catalog.xml");
+        assert_eq!(ContentType::from(data), ContentType::Xml);
+
+        let data: &[u8] = b"<feed><entry>no declaration</entry></feed>";
+        assert_eq!(ContentType::from(data), ContentType::Xml);
+    }
+
+    #[test]
+    fn test_detect_calendar_and_contacts() {
+        let data: &[u8] = include_bytes!("../tests/data/invite.ics");
+        assert_eq!(ContentType::from(data), ContentType::ICalendar);
+
+        let data: &[u8] = include_bytes!("../tests/data/contacts.vcf");
+        assert_eq!(ContentType::from(data), ContentType::VCard);
+    }
+
+    #[test]
+    fn test_detect_non_hwp_compound_file() {
+        let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        file.create_stream("/FileHeader").unwrap();
+        file.flush().unwrap();
+        let data = file.into_inner().into_inner();
+
+        assert_ne!(ContentType::from(data.as_slice()), ContentType::Hwp);
+    }
+
+    #[test]
+    fn test_detect_text_starting_like_chm() {
+        let data: &[u8] = b"ITSF meeting notes\nBudget review moved to Friday.\n";
+
+        assert_eq!(ContentType::from(data), ContentType::Txt);
+    }
+
+    #[test]
+    fn test_detect_json() {
+        let data: &[u8] = include_bytes!("../tests/data/orders.json");
+        let content_type = ContentType::from(data);
+
+        assert_eq!(content_type, ContentType::Json);
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,29 @@
+use std::fmt;
+
+///
+/// Errors that callers may want to handle differently from a generic extraction failure. They
+/// are returned wrapped in an `anyhow::Error`, so use `error.downcast_ref::<ExtractionError>()`
+/// to check for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionError {
+    /// The document is encrypted and no password was supplied.
+    PasswordRequired,
+    /// The supplied password is neither the user nor the owner password of the document.
+    IncorrectPassword,
+    /// The document is malformed in a way the parser could not recover from.
+    CorruptDocument(String),
+}
+
+impl fmt::Display for ExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtractionError::PasswordRequired => write!(f, "the document is password protected"),
+            ExtractionError::IncorrectPassword => write!(f, "the supplied password is incorrect"),
+            ExtractionError::CorruptDocument(reason) => {
+                write!(f, "the document is corrupt: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExtractionError {}
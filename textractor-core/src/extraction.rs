@@ -2,85 +2,49 @@ use std::io::{Cursor, Read};
 use zip::ZipArchive;
 use xml::reader::{EventReader, XmlEvent};
 use anyhow::{Context, Result};
-use docx_rs::read_docx;
 use scraper::{Html, Selector};
-use pdf_extract::extract_text_from_mem;
 
 use crate::detection::ContentType;
 
+mod boilerplate;
+pub mod calendar;
+pub mod chm;
+mod content_line;
+mod dates;
+pub mod docx;
+pub mod hwp;
+mod mime;
+pub mod ooxml;
+pub mod pdf;
+pub mod structured;
+pub mod vcard;
+pub mod webpage;
+
+pub use boilerplate::BoilerplateLine;
+pub use calendar::{CalendarEvent, IcsExtractor};
+pub use chm::ChmExtractor;
+pub use docx::{
+    DocxCell, DocxDocument, DocxExtractor, DocxHeading, DocxMath, DocxOptions, DocxPart,
+    DocxPartKind, DocxTable, DocxTrackedChanges,
+};
+pub use hwp::{HwpExtractor, HwpxExtractor};
+pub use ooxml::OoxmlMetadata;
+pub use pdf::{
+    Annotation, AnnotationKind, FormField, FormFieldKind, OutlineItem, PdfAttachment, PdfCleanup,
+    PdfDocument, PdfExtractor, PdfLayout, PdfMetadata, PdfOptions, PdfPage, PdfTable,
+};
+pub use structured::{JsonExtractor, StructuredOptions, TextValue, XmlExtractor};
+pub use vcard::{Contact, VcfExtractor};
+pub use webpage::{MhtmlExtractor, WebArchiveExtractor, WebPageOptions};
+
 pub trait Extract {
     fn extract(data: &[u8]) -> Result<String, anyhow::Error>;
 }
 
-pub struct PdfExtractor;
-pub struct DocxExtractor;
 pub struct PptxExtractor;
 pub struct TxtExtractor;
 pub struct HtmlExtractor;
 
-impl Extract for PdfExtractor {
-    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
-        let text = extract_text_from_mem(data)?;
-        Ok(text)
-    }
-}
-
-impl Extract for DocxExtractor {
-    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
-        let doc = read_docx(data)?;
-        let mut document_text = String::new();
-        let children = doc.document.children;
-        for child in children {
-            match child {
-                docx_rs::DocumentChild::Paragraph(paragraph) => {
-                    for child in paragraph.children {
-                        match child {
-                            docx_rs::ParagraphChild::Run(run) => {
-                                for child in run.children {
-                                    match child {
-                                        docx_rs::RunChild::Text(text) => {
-                                            document_text.push_str(&text.text);
-                                            document_text.push('\n'); // push a space for new lines
-                                        }
-                                        docx_rs::RunChild::Sym(_) => (),
-                                        docx_rs::RunChild::DeleteText(_) => (),
-                                        docx_rs::RunChild::Tab(_) => (),
-                                        docx_rs::RunChild::Break(_) => (),
-                                        docx_rs::RunChild::Drawing(_) => (),
-                                        docx_rs::RunChild::Shape(_) => (),
-                                        docx_rs::RunChild::CommentStart(_) => (),
-                                        docx_rs::RunChild::CommentEnd(_) => (),
-                                        docx_rs::RunChild::FieldChar(_) => (),
-                                        docx_rs::RunChild::InstrText(_) => (),
-                                        docx_rs::RunChild::DeleteInstrText(_) => (),
-                                        docx_rs::RunChild::InstrTextString(_) => (),
-                                    }
-                                }
-                            }
-                            docx_rs::ParagraphChild::Insert(_) => (),
-                            docx_rs::ParagraphChild::Delete(_) => (),
-                            docx_rs::ParagraphChild::BookmarkStart(_) => (),
-                            docx_rs::ParagraphChild::Hyperlink(_) => (),
-                            docx_rs::ParagraphChild::BookmarkEnd(_) => (),
-                            docx_rs::ParagraphChild::CommentStart(_) => (),
-                            docx_rs::ParagraphChild::CommentEnd(_) => (),
-                            docx_rs::ParagraphChild::StructuredDataTag(_) => (),
-                        }
-                    }
-                }
-                docx_rs::DocumentChild::Table(_) => (),
-                docx_rs::DocumentChild::BookmarkStart(_) => (),
-                docx_rs::DocumentChild::BookmarkEnd(_) => (),
-                docx_rs::DocumentChild::CommentStart(_) => (),
-                docx_rs::DocumentChild::CommentEnd(_) => (),
-                docx_rs::DocumentChild::StructuredDataTag(_) => (),
-                docx_rs::DocumentChild::TableOfContents(_) => (),
-            }
-        }
-        Ok(document_text)
-    }
-}
-
 impl Extract for PptxExtractor {
     fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
        // Open the PPTX file as a zip archive
@@ -195,7 +159,33 @@ pub fn extract(data: &[u8]) -> Result<Option<String>> {
         ContentType::Epub => None, // TODO: implement epub extractor
         ContentType::Mobi => None, // TODO: implement epub extractor
         ContentType::Html => Some(HtmlExtractor::extract(data)?), // TODO: implement html extractor
+        ContentType::Xml => Some(XmlExtractor::extract(data)?),
+        ContentType::Json => Some(JsonExtractor::extract(data)?),
+        ContentType::ICalendar => Some(IcsExtractor::extract(data)?),
+        ContentType::VCard => Some(VcfExtractor::extract(data)?),
+        ContentType::Hwp => Some(HwpExtractor::extract(data)?),
+        ContentType::Hwpx => Some(HwpxExtractor::extract(data)?),
+        ContentType::Mhtml => Some(MhtmlExtractor::extract(data)?),
+        ContentType::WebArchive => Some(WebArchiveExtractor::extract(data)?),
+        ContentType::Chm => Some(ChmExtractor::extract(data)?),
         ContentType::Unknown => Some(TxtExtractor::extract(data)?) // Try to extract anyways as plain-text
     };
     Ok(result)
 }
+
+///
+/// Like [`extract`], but decrypts password protected PDFs with the given user or owner password.
+/// Encrypted PDFs return an [`ExtractionError`](crate::errors::ExtractionError) when the password
+/// is missing or wrong.
+pub fn extract_with_password(data: &[u8], password: &str) -> Result<Option<String>> {
+    match ContentType::from(data) {
+        ContentType::Pdf => {
+            let options = PdfOptions {
+                password: Some(password.to_string()),
+                ..Default::default()
+            };
+            Ok(Some(PdfExtractor::extract_document(data, &options)?.text()))
+        }
+        _ => extract(data),
+    }
+}
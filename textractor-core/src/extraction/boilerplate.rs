@@ -0,0 +1,203 @@
+//! Removal of running headers, footers and page numbers from paginated documents.
+
+use std::collections::{HashMap, HashSet};
+
+/// A line removed from a page as a running header, footer or page number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoilerplateLine {
+    /// 1-based number of the page the line was removed from.
+    pub page: u32,
+    pub text: String,
+}
+
+// headers and footers are searched for among the first and last lines of each page
+const EDGE_LINES: usize = 3;
+// a line is boilerplate when it repeats on at least this many pages...
+const MIN_PAGES: usize = 3;
+// ...and on at least this fraction of them (running headers often alternate between pages)
+const MIN_FRACTION: f64 = 0.4;
+
+fn is_roman_numeral(word: &str) -> bool {
+    !word.is_empty()
+        && word.len() <= 6
+        && word
+            .chars()
+            .all(|c| matches!(c.to_ascii_lowercase(), 'i' | 'v' | 'x' | 'l' | 'c'))
+}
+
+///
+/// The line with its numbers (and lone roman numerals) replaced by `#` and its case and spacing
+/// normalized, so that "Page 3 of 12" and "Page 4 of 12" compare equal.
+fn pattern(line: &str) -> String {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut pattern = String::new();
+    for word in &words {
+        if !pattern.is_empty() {
+            pattern.push(' ');
+        }
+        let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if words.len() <= 3 && is_roman_numeral(bare) {
+            pattern.push_str(&word.replace(bare, "#"));
+            continue;
+        }
+        let mut digits = false;
+        for c in word.chars().flat_map(char::to_lowercase) {
+            match c.is_ascii_digit() {
+                true if digits => {}
+                true => pattern.push('#'),
+                false => pattern.push(c),
+            }
+            digits = c.is_ascii_digit();
+        }
+    }
+    pattern
+}
+
+/// The indices of the first and last non-empty lines of a page.
+fn edge_lines(lines: &[&str]) -> Vec<usize> {
+    let filled: Vec<usize> = (0..lines.len())
+        .filter(|i| !lines[*i].trim().is_empty())
+        .collect();
+    let mut edges: Vec<usize> = filled.iter().take(EDGE_LINES).copied().collect();
+    edges.extend(filled.iter().rev().take(EDGE_LINES));
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+///
+/// Finds lines repeated near the top or bottom of many pages, allowing for changing numbers, and
+/// removes them from the page texts. Returns the removed lines, in page order.
+pub(crate) fn remove_boilerplate(pages: &mut [(u32, &mut String)]) -> Vec<BoilerplateLine> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, text) in pages.iter() {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let patterns: HashSet<String> = edge_lines(&lines)
+            .into_iter()
+            .map(|i| pattern(lines[i]))
+            .collect();
+        for pattern in patterns {
+            *counts.entry(pattern).or_default() += 1;
+        }
+    }
+    let threshold = MIN_PAGES.max((pages.len() as f64 * MIN_FRACTION).ceil() as usize);
+    let repeated: HashSet<String> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= threshold)
+        .map(|(pattern, _)| pattern)
+        .collect();
+    if repeated.is_empty() {
+        return Vec::new();
+    }
+
+    let mut removed = Vec::new();
+    for (number, text) in pages.iter_mut() {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let boilerplate: HashSet<usize> = edge_lines(&lines)
+            .into_iter()
+            .filter(|i| repeated.contains(&pattern(lines[*i])))
+            .collect();
+        if boilerplate.is_empty() {
+            continue;
+        }
+
+        let mut kept: Vec<&str> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            if boilerplate.contains(&i) {
+                removed.push(BoilerplateLine {
+                    page: *number,
+                    text: line.trim().to_string(),
+                });
+            // don't leave two blank lines where a line between paragraphs was removed
+            } else if !(line.trim().is_empty()
+                && i > 0
+                && boilerplate.contains(&(i - 1))
+                && kept.last().is_none_or(|last| last.trim().is_empty()))
+            {
+                kept.push(line);
+            }
+        }
+        **text = kept.join("\n");
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn clean(texts: &[&str]) -> (Vec<String>, Vec<BoilerplateLine>) {
+        let mut texts: Vec<String> = texts.iter().map(|text| text.to_string()).collect();
+        let mut pages: Vec<(u32, &mut String)> = texts
+            .iter_mut()
+            .enumerate()
+            .map(|(i, text)| (i as u32 + 1, text))
+            .collect();
+        let removed = remove_boilerplate(&mut pages);
+        (texts, removed)
+    }
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(pattern("Page 3 of 12"), pattern("page 10  of 12"));
+        assert_eq!(pattern("- iv -"), "- # -");
+        assert_eq!(pattern("Vol. 2, 2019"), "vol. #, #");
+        assert_ne!(pattern("Chapter One"), pattern("Chapter Two"));
+    }
+
+    #[test]
+    fn test_remove_boilerplate() {
+        let (texts, removed) = clean(&[
+            "ACME Annual Report\n\nIntroduction to the year.\n\nPage 1 of 4",
+            "ACME Annual Report\n\nRevenue grew by 12 percent.\n\nPage 2 of 4",
+            "ACME Annual Report\n\nCosts were flat.\n\nPage 3 of 4",
+            "Appendix tables.\n\nPage 4 of 4",
+        ]);
+
+        assert_eq!(
+            texts,
+            vec![
+                "Introduction to the year.\n",
+                "Revenue grew by 12 percent.\n",
+                "Costs were flat.\n",
+                "Appendix tables.\n",
+            ]
+        );
+        assert_eq!(removed.len(), 7);
+        assert_eq!(
+            removed[0],
+            BoilerplateLine {
+                page: 1,
+                text: "ACME Annual Report".to_string()
+            }
+        );
+        assert_eq!(
+            removed[6],
+            BoilerplateLine {
+                page: 4,
+                text: "Page 4 of 4".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_body_lines_are_kept() {
+        // repeated, but in the middle of long pages
+        let (texts, _) = clean(&[
+            "Alpha\nBeta\nGamma\nSee the appendix.\nDelta\nEpsilon\nZeta",
+            "One\nTwo\nThree\nSee the appendix.\nFour\nFive\nSix",
+            "Red\nGreen\nBlue\nSee the appendix.\nCyan\nMagenta\nYellow",
+        ]);
+
+        assert!(texts.iter().all(|text| text.contains("See the appendix.")));
+    }
+
+    #[test]
+    fn test_too_few_pages() {
+        let (texts, removed) = clean(&["Header\nBody one", "Header\nBody two"]);
+
+        assert_eq!(texts, vec!["Header\nBody one", "Header\nBody two"]);
+        assert!(removed.is_empty());
+    }
+}
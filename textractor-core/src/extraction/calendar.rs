@@ -0,0 +1,194 @@
+use anyhow::{bail, Result};
+
+use super::content_line::{self, format_datetime, Property};
+use super::Extract;
+
+pub struct IcsExtractor;
+
+/// An event (`VEVENT`) or task (`VTODO`) from an iCalendar file, with values already decoded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CalendarEvent {
+    pub kind: String,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub organizer: Option<String>,
+    pub attendees: Vec<String>,
+}
+
+impl CalendarEvent {
+    fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        let label = if self.kind == "VTODO" {
+            "Task"
+        } else {
+            "Event"
+        };
+        lines.push(format!(
+            "{}: {}",
+            label,
+            self.summary.as_deref().unwrap_or("(untitled)")
+        ));
+        match (&self.start, &self.end) {
+            (Some(start), Some(end)) => lines.push(format!("When: {} - {}", start, end)),
+            (Some(start), None) => lines.push(format!("When: {}", start)),
+            (None, Some(end)) => lines.push(format!("Due: {}", end)),
+            (None, None) => {}
+        }
+        if let Some(location) = &self.location {
+            lines.push(format!("Location: {}", location));
+        }
+        if let Some(organizer) = &self.organizer {
+            lines.push(format!("Organizer: {}", organizer));
+        }
+        if !self.attendees.is_empty() {
+            lines.push(format!("Attendees: {}", self.attendees.join(", ")));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("Description: {}", description));
+        }
+        lines.join("\n")
+    }
+}
+
+/// The display name of a `CAL-ADDRESS` property, falling back to the address itself.
+fn calendar_address(property: &Property) -> String {
+    let address = property.value.trim();
+    let address = address
+        .strip_prefix("mailto:")
+        .or_else(|| address.strip_prefix("MAILTO:"))
+        .unwrap_or(address);
+    match property.param("CN") {
+        Some(name) if !name.is_empty() => format!("{} <{}>", name, address),
+        _ => address.to_string(),
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+impl IcsExtractor {
+    ///
+    /// Parses the events and tasks of an iCalendar file. Nested components such as
+    /// `VALARM` are skipped so their descriptions do not overwrite the event's own.
+    pub fn events(data: &[u8]) -> Result<Vec<CalendarEvent>> {
+        let text = String::from_utf8_lossy(data);
+        let properties = content_line::parse(&text);
+        if !properties
+            .first()
+            .is_some_and(|p| p.name == "BEGIN" && p.value.eq_ignore_ascii_case("VCALENDAR"))
+        {
+            bail!("Not an iCalendar file: missing BEGIN:VCALENDAR");
+        }
+
+        let mut events = Vec::new();
+        let mut current: Option<CalendarEvent> = None;
+        let mut nested = 0;
+
+        for property in &properties {
+            match property.name.as_str() {
+                "BEGIN" => {
+                    let component = property.value.trim().to_ascii_uppercase();
+                    if current.is_some() {
+                        nested += 1;
+                    } else if component == "VEVENT" || component == "VTODO" {
+                        current = Some(CalendarEvent {
+                            kind: component,
+                            ..Default::default()
+                        });
+                    }
+                }
+                "END" => {
+                    if nested > 0 {
+                        nested -= 1;
+                    } else if let Some(event) = current.take() {
+                        events.push(event);
+                    }
+                }
+                _ if nested > 0 => {}
+                name => {
+                    let Some(event) = current.as_mut() else {
+                        continue;
+                    };
+                    match name {
+                        "SUMMARY" => event.summary = non_empty(property.text()),
+                        "DESCRIPTION" => event.description = non_empty(property.text()),
+                        "LOCATION" => event.location = non_empty(property.text()),
+                        "DTSTART" => {
+                            event.start =
+                                Some(format_datetime(&property.value, property.param("TZID")))
+                        }
+                        "DTEND" | "DUE" => {
+                            event.end =
+                                Some(format_datetime(&property.value, property.param("TZID")))
+                        }
+                        "ORGANIZER" => event.organizer = Some(calendar_address(property)),
+                        "ATTENDEE" => event.attendees.push(calendar_address(property)),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+impl Extract for IcsExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        let text = String::from_utf8_lossy(data);
+        let calendar_name = content_line::parse(&text)
+            .into_iter()
+            .find(|p| p.name == "X-WR-CALNAME")
+            .and_then(|p| non_empty(p.text()));
+
+        let mut blocks: Vec<String> = Self::events(data)?
+            .iter()
+            .map(CalendarEvent::to_text)
+            .collect();
+        if let Some(name) = calendar_name {
+            blocks.insert(0, format!("Calendar: {}", name));
+        }
+        Ok(blocks.join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const INVITE: &[u8] = include_bytes!("../../tests/data/invite.ics");
+
+    #[test]
+    fn test_parse_events() {
+        let events = IcsExtractor::events(INVITE).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0].summary.as_deref(),
+            Some("Quarterly planning, budget review")
+        );
+        assert_eq!(events[0].start.as_deref(), Some("2024-03-04 14:00 UTC"));
+        assert_eq!(
+            events[0].organizer.as_deref(),
+            Some("Grace Hopper <grace@example.com>")
+        );
+        assert_eq!(events[1].kind, "VTODO");
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let text = IcsExtractor::extract(INVITE).unwrap();
+
+        assert!(text.starts_with("Calendar: Team calendar\n\nEvent: Quarterly planning"));
+        assert!(text.contains("Location: Room 4; Building B"));
+        assert!(text.contains("Description: Agenda:\n1. Budget\n2. Hiring plan for the new office"));
+        assert!(!text.contains("Reminder"));
+        assert!(text.contains("Task: Send slides\nDue: 2024-03-01"));
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+use lzxd::{Lzxd, WindowSize};
+use scraper::{Html, Selector};
+
+use super::{Extract, HtmlExtractor};
+
+pub struct ChmExtractor;
+
+const CONTENT: &str = "::DataSpace/Storage/MSCompressed/Content";
+const CONTROL_DATA: &str = "::DataSpace/Storage/MSCompressed/ControlData";
+const RESET_TABLE: &str = "::DataSpace/Storage/MSCompressed/Transform/\
+                           {7FC28940-9D31-11D0-9B27-00A0C91E9C7C}/InstanceData/ResetTable";
+
+/// LZX frames always decompress to 32 KB, except for the last one.
+const FRAME_SIZE: usize = 0x8000;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    section: u64,
+    offset: u64,
+    length: u64,
+}
+
+/// The range of `length` bytes at `start`, failing when offsets read from the file overflow.
+fn range(start: usize, length: usize) -> Result<Range<usize>> {
+    match start.checked_add(length) {
+        Some(end) => Ok(start..end),
+        None => bail!("CHM offset {:#x} + {:#x} overflows", start, length),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data
+        .get(range(offset, 4)?)
+        .context("Unexpected end of CHM data")?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data
+        .get(range(offset, 8)?)
+        .context("Unexpected end of CHM data")?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a variable length big-endian integer with 7 bits per byte (the high bit marks continuation).
+fn read_encint(data: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    loop {
+        let byte = *data.get(*pos).context("Truncated CHM directory entry")?;
+        *pos += 1;
+        value = (value << 7) | u64::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+fn window_size(bytes: u32) -> Result<WindowSize> {
+    Ok(match bytes {
+        0x0000_8000 => WindowSize::KB32,
+        0x0001_0000 => WindowSize::KB64,
+        0x0002_0000 => WindowSize::KB128,
+        0x0004_0000 => WindowSize::KB256,
+        0x0008_0000 => WindowSize::KB512,
+        0x0010_0000 => WindowSize::MB1,
+        0x0020_0000 => WindowSize::MB2,
+        _ => bail!("Unsupported LZX window size {:#x}", bytes),
+    })
+}
+
+struct ChmFile<'a> {
+    data: &'a [u8],
+    content_offset: usize,
+    entries: HashMap<String, Entry>,
+    /// Entry names in directory order.
+    names: Vec<String>,
+    compressed: Option<Vec<u8>>,
+}
+
+impl<'a> ChmFile<'a> {
+    fn open(data: &'a [u8]) -> Result<ChmFile<'a>> {
+        if !data.starts_with(b"ITSF") {
+            bail!("Not a CHM file: missing ITSF signature");
+        }
+        let version = read_u32(data, 4)?;
+        let directory_offset = read_u64(data, 0x48)? as usize;
+        let directory_length = read_u64(data, 0x50)? as usize;
+        let content_offset = if version >= 3 {
+            read_u64(data, 0x58)? as usize
+        } else {
+            range(directory_offset, directory_length)?.end
+        };
+
+        let directory = data
+            .get(range(directory_offset, directory_length)?)
+            .context("CHM directory is out of bounds")?;
+        if !directory.starts_with(b"ITSP") {
+            bail!("CHM directory is missing its ITSP header");
+        }
+        let header_length = read_u32(directory, 8)? as usize;
+        let chunk_size = read_u32(directory, 0x10)? as usize;
+        let chunk_count = read_u32(directory, 0x2C)? as usize;
+        if chunk_size < 20 {
+            bail!("Invalid CHM directory chunk size {}", chunk_size);
+        }
+
+        let mut entries = HashMap::new();
+        let mut names = Vec::new();
+        for index in 0..chunk_count {
+            let Some(start) = index
+                .checked_mul(chunk_size)
+                .and_then(|offset| offset.checked_add(header_length))
+            else {
+                bail!("CHM directory chunk {} is out of range", index);
+            };
+            let Some(chunk) = directory.get(range(start, chunk_size)?) else {
+                break;
+            };
+            // PMGI chunks only index the PMGL listing chunks, so they can be skipped
+            if !chunk.starts_with(b"PMGL") {
+                continue;
+            }
+
+            let end = chunk_size.saturating_sub(read_u32(chunk, 4)? as usize);
+            let mut pos = 20;
+            while pos < end {
+                let name_length = read_encint(chunk, &mut pos)? as usize;
+                let name = chunk
+                    .get(range(pos, name_length)?)
+                    .context("Truncated CHM directory entry")?;
+                pos += name_length;
+                let entry = Entry {
+                    section: read_encint(chunk, &mut pos)?,
+                    offset: read_encint(chunk, &mut pos)?,
+                    length: read_encint(chunk, &mut pos)?,
+                };
+
+                let name = String::from_utf8_lossy(name).to_string();
+                names.push(name.clone());
+                entries.insert(name, entry);
+            }
+        }
+
+        Ok(ChmFile {
+            data,
+            content_offset,
+            entries,
+            names,
+            compressed: None,
+        })
+    }
+
+    fn find(&self, name: &str) -> Option<Entry> {
+        self.entries.get(name).copied().or_else(|| {
+            self.entries
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, entry)| *entry)
+        })
+    }
+
+    fn read_uncompressed(&self, entry: Entry) -> Result<&'a [u8]> {
+        let start = range(self.content_offset, entry.offset as usize)?.end;
+        self.data
+            .get(range(start, entry.length as usize)?)
+            .context("CHM file content is out of bounds")
+    }
+
+    fn read_section_file(&self, name: &str) -> Result<&'a [u8]> {
+        let entry = self
+            .find(name)
+            .with_context(|| format!("CHM file is missing {}", name))?;
+        self.read_uncompressed(entry)
+    }
+
+    ///
+    /// Decompresses the whole MSCompressed section. The reset table lists the compressed
+    /// offset of every 32 KB frame, and the decoder state is reset every `reset_interval` frames.
+    fn decompress(&self) -> Result<Vec<u8>> {
+        let control = self.read_section_file(CONTROL_DATA)?;
+        if control.get(4..8) != Some(b"LZXC") {
+            bail!("CHM compressed section is not LZX");
+        }
+        let version = read_u32(control, 8)?;
+        let mut reset_interval = read_u32(control, 12)? as usize;
+        let mut window = read_u32(control, 16)?;
+        if version == 2 {
+            let (Some(interval), Some(size)) = (
+                reset_interval.checked_mul(FRAME_SIZE),
+                window.checked_mul(FRAME_SIZE as u32),
+            ) else {
+                bail!("Invalid LZX reset interval or window size");
+            };
+            (reset_interval, window) = (interval, size);
+        }
+        let frames_per_reset = (reset_interval / FRAME_SIZE).max(1);
+
+        let table = self.read_section_file(RESET_TABLE)?;
+        let frame_count = read_u32(table, 4)? as usize;
+        let table_offset = read_u32(table, 12)? as usize;
+        let uncompressed_length = read_u64(table, 16)? as usize;
+        let offsets = (0..frame_count)
+            .map(|i| read_u64(table, range(table_offset, i * 8)?.end).map(|o| o as usize))
+            .collect::<Result<Vec<_>>>()?;
+
+        let content = self.read_section_file(CONTENT)?;
+        let mut decoder = Lzxd::new(window_size(window)?);
+        // the length comes from the file: only trust it as far as LZX can plausibly expand
+        let mut output =
+            Vec::with_capacity(uncompressed_length.min(content.len().saturating_mul(64)));
+
+        for (frame, start) in offsets.iter().enumerate() {
+            if frame % frames_per_reset == 0 {
+                decoder.reset();
+            }
+            let end = offsets.get(frame + 1).copied().unwrap_or(content.len());
+            let chunk = content
+                .get(*start..end)
+                .context("CHM reset table points outside the compressed content")?;
+            let length = FRAME_SIZE.min(uncompressed_length.saturating_sub(output.len()));
+            if length == 0 {
+                break;
+            }
+            let decompressed = decoder
+                .decompress_next(chunk, length)
+                .map_err(|e| anyhow::anyhow!("Failed to decompress CHM content: {}", e))?;
+            output.extend_from_slice(decompressed);
+        }
+
+        Ok(output)
+    }
+
+    fn read(&mut self, entry: Entry) -> Result<Vec<u8>> {
+        match entry.section {
+            0 => Ok(self.read_uncompressed(entry)?.to_vec()),
+            1 => {
+                if self.compressed.is_none() {
+                    self.compressed = Some(self.decompress()?);
+                }
+                let section = self.compressed.as_deref().unwrap_or_default();
+                section
+                    .get(range(entry.offset as usize, entry.length as usize)?)
+                    .map(<[u8]>::to_vec)
+                    .context("CHM file content is out of bounds")
+            }
+            other => bail!("Unknown CHM content section {}", other),
+        }
+    }
+}
+
+/// Resolves a topic link from the table of contents to a directory entry name.
+fn topic_name(local: &str) -> String {
+    let local = local.split('#').next().unwrap_or(local);
+    let local = local
+        .rsplit_once("::")
+        .map(|(_, path)| path)
+        .unwrap_or(local);
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in local.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Lists the `Local` topic links of a `.hhc` sitemap in table of contents order.
+fn toc_topics(hhc: &[u8]) -> Vec<String> {
+    let document = Html::parse_document(&String::from_utf8_lossy(hhc));
+    let selector = Selector::parse("object param").unwrap();
+
+    document
+        .select(&selector)
+        .filter(|param| {
+            param
+                .value()
+                .attr("name")
+                .is_some_and(|name| name.eq_ignore_ascii_case("local"))
+        })
+        .filter_map(|param| param.value().attr("value"))
+        .map(topic_name)
+        .collect()
+}
+
+fn is_html_topic(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    name.starts_with('/')
+        && !name.starts_with("/#")
+        && !name.starts_with("/$")
+        && (lower.ends_with(".htm") || lower.ends_with(".html"))
+}
+
+impl Extract for ChmExtractor {
+    ///
+    /// Extracts the HTML topics of a CHM file, following the order of its table of contents.
+    /// Topics that the table of contents does not reference are appended in directory order.
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        let mut chm = ChmFile::open(data)?;
+
+        let mut order = Vec::new();
+        let hhc = chm
+            .names
+            .iter()
+            .find(|name| name.to_ascii_lowercase().ends_with(".hhc"))
+            .cloned();
+        if let Some(hhc) = hhc {
+            let entry = chm.entries[&hhc];
+            order.extend(toc_topics(&chm.read(entry)?));
+        }
+        order.extend(chm.names.iter().filter(|name| is_html_topic(name)).cloned());
+
+        let mut seen = HashSet::new();
+        let mut topics = Vec::new();
+        for name in order {
+            let Some(entry) = chm.find(&name) else {
+                continue;
+            };
+            if !seen.insert(name.to_ascii_lowercase()) || entry.length == 0 {
+                continue;
+            }
+            let text = HtmlExtractor::extract(&chm.read(entry)?)?;
+            if !text.trim().is_empty() {
+                topics.push(text.trim().to_string());
+            }
+        }
+
+        Ok(topics.join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::ContentType;
+    use pretty_assertions::assert_eq;
+
+    fn encint(mut value: u64) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    /// Wraps data in a single uncompressed LZX block (no E8 translation).
+    fn lzx_uncompressed(data: &[u8]) -> Vec<u8> {
+        // 1 bit E8 flag, 3 bit block type (3 = uncompressed), 24 bit block size, then padding
+        let header: u32 = (0b0011 << 24) | data.len() as u32;
+        let words = [(header >> 12) as u16, ((header & 0xFFF) << 4) as u16];
+
+        let mut out: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        for r in [1u32, 1, 1] {
+            out.extend_from_slice(&r.to_le_bytes());
+        }
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    fn chm_file(compressed_files: &[(&str, &str)]) -> Vec<u8> {
+        let mut section1 = Vec::new();
+        let mut entries: Vec<(String, u64, u64, u64)> = Vec::new();
+        for (name, body) in compressed_files {
+            entries.push((
+                name.to_string(),
+                1,
+                section1.len() as u64,
+                body.len() as u64,
+            ));
+            section1.extend_from_slice(body.as_bytes());
+        }
+
+        let mut control = Vec::new();
+        for value in [6u32, u32::from_le_bytes(*b"LZXC"), 2, 2, 2, 0, 0] {
+            control.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut reset_table = Vec::new();
+        for value in [2u32, 1, 8, 0x28] {
+            reset_table.extend_from_slice(&value.to_le_bytes());
+        }
+        reset_table.extend_from_slice(&(section1.len() as u64).to_le_bytes());
+        let content = lzx_uncompressed(&section1);
+        reset_table.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        reset_table.extend_from_slice(&(FRAME_SIZE as u64).to_le_bytes());
+        reset_table.extend_from_slice(&0u64.to_le_bytes());
+
+        let mut section0 = Vec::new();
+        for (name, data) in [
+            (CONTROL_DATA, control),
+            (RESET_TABLE, reset_table),
+            (CONTENT, content),
+        ] {
+            entries.push((
+                name.to_string(),
+                0,
+                section0.len() as u64,
+                data.len() as u64,
+            ));
+            section0.extend_from_slice(&data);
+        }
+
+        let chunk_size = 4096usize;
+        let mut chunk = b"PMGL".to_vec();
+        chunk.extend_from_slice(&[0; 16]);
+        for (name, section, offset, length) in &entries {
+            chunk.extend(encint(name.len() as u64));
+            chunk.extend_from_slice(name.as_bytes());
+            chunk.extend(encint(*section));
+            chunk.extend(encint(*offset));
+            chunk.extend(encint(*length));
+        }
+        let free_space = (chunk_size - chunk.len()) as u32;
+        chunk[4..8].copy_from_slice(&free_space.to_le_bytes());
+        chunk.resize(chunk_size, 0);
+
+        let mut directory = b"ITSP".to_vec();
+        directory.resize(0x54, 0);
+        directory[8..12].copy_from_slice(&0x54u32.to_le_bytes());
+        directory[0x10..0x14].copy_from_slice(&(chunk_size as u32).to_le_bytes());
+        directory[0x2C..0x30].copy_from_slice(&1u32.to_le_bytes());
+        directory.extend(chunk);
+
+        let mut file = b"ITSF".to_vec();
+        file.resize(0x60, 0);
+        file[4..8].copy_from_slice(&3u32.to_le_bytes());
+        file[8..12].copy_from_slice(&0x60u32.to_le_bytes());
+        file[0x48..0x50].copy_from_slice(&0x60u64.to_le_bytes());
+        file[0x50..0x58].copy_from_slice(&(directory.len() as u64).to_le_bytes());
+        file[0x58..0x60].copy_from_slice(&(0x60 + directory.len() as u64).to_le_bytes());
+        file.extend(directory);
+        file.extend(section0);
+        file
+    }
+
+    #[test]
+    fn test_extract_follows_toc() {
+        let data = chm_file(&[
+            ("/appendix.htm", "<html><body><p>Appendix</p></body></html>"),
+            (
+                "/intro.htm",
+                "<html><body><h1>Introduction</h1></body></html>",
+            ),
+            (
+                "/topics/setup.htm",
+                "<html><body><p>Setup steps</p></body></html>",
+            ),
+            (
+                "/toc.hhc",
+                r#"<html><body><ul>
+                    <li><object type="text/sitemap"><param name="Name" value="Intro"><param name="Local" value="intro.htm"></object>
+                    <ul><li><object type="text/sitemap"><param name="Name" value="Setup"><param name="Local" value="topics/setup.htm#install"></object></ul>
+                </ul></body></html>"#,
+            ),
+        ]);
+
+        assert_eq!(ContentType::from(data.as_slice()), ContentType::Chm);
+        assert_eq!(
+            ChmExtractor::extract(&data).unwrap(),
+            "Introduction\n\nSetup steps\n\nAppendix"
+        );
+    }
+
+    #[test]
+    fn test_overflowing_offsets() {
+        let mut file = b"ITSF".to_vec();
+        file.resize(0x60, 0);
+        file[4..8].copy_from_slice(&2u32.to_le_bytes());
+        file[0x48..0x50].copy_from_slice(&u64::MAX.to_le_bytes());
+        file[0x50..0x58].copy_from_slice(&0x54u64.to_le_bytes());
+
+        assert!(ChmExtractor::extract(&file).is_err());
+    }
+
+    #[test]
+    fn test_topic_name() {
+        assert_eq!(topic_name("topics/../intro.htm#top"), "/intro.htm");
+        assert_eq!(topic_name("help.chm::/html/page.htm"), "/html/page.htm");
+    }
+}
@@ -0,0 +1,237 @@
+//! Shared parsing for the RFC 5545 (iCalendar) and RFC 6350 (vCard) content line format.
+
+use super::mime::{decode_charset, decode_quoted_printable};
+
+/// A single unfolded `NAME;PARAM=value:value` content line.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value with quoted-printable decoded and text escapes resolved.
+    pub fn text(&self) -> String {
+        unescape(&self.decoded())
+    }
+
+    /// The value split on unescaped `;`, each component unescaped (used by `N`, `ORG`, `ADR`).
+    pub fn components(&self) -> Vec<String> {
+        split_unescaped(&self.decoded(), ';')
+            .iter()
+            .map(|c| unescape(c))
+            .collect()
+    }
+
+    fn decoded(&self) -> String {
+        let quoted_printable = self
+            .param("ENCODING")
+            .is_some_and(|e| e.eq_ignore_ascii_case("QUOTED-PRINTABLE"))
+            || self.params.iter().any(|(key, value)| {
+                value.is_empty() && key.eq_ignore_ascii_case("QUOTED-PRINTABLE")
+            });
+
+        if quoted_printable {
+            let bytes = decode_quoted_printable(self.value.as_bytes());
+            decode_charset(&bytes, self.param("CHARSET"))
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+///
+/// Unfolds and parses content lines. Continuation lines start with a space or tab; vCard 2.1
+/// quoted-printable values also continue onto the next line when they end in a soft break (`=`).
+pub(crate) fn parse(text: &str) -> Vec<Property> {
+    let mut logical: Vec<String> = Vec::new();
+    let mut soft_break = false;
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(last) = logical.last_mut() {
+            if soft_break {
+                last.pop();
+                last.push_str(line.trim_start());
+                soft_break = is_soft_break(last);
+                continue;
+            }
+            if line.starts_with(' ') || line.starts_with('\t') {
+                last.push_str(&line[1..]);
+                soft_break = is_soft_break(last);
+                continue;
+            }
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        logical.push(line.to_string());
+        soft_break = is_soft_break(logical.last().unwrap());
+    }
+
+    logical.iter().filter_map(|line| parse_line(line)).collect()
+}
+
+fn is_soft_break(line: &str) -> bool {
+    line.ends_with('=') && line.to_ascii_uppercase().contains("QUOTED-PRINTABLE")
+}
+
+fn parse_line(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = split_unescaped(head, ';').into_iter();
+    let name = parts.next()?;
+    // vCard allows a `group.` prefix on property names
+    let name = name
+        .rsplit('.')
+        .next()
+        .unwrap_or(&name)
+        .to_ascii_uppercase();
+
+    let params = parts
+        .map(|param| match param.split_once('=') {
+            Some((key, value)) => (
+                key.to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            ),
+            None => (param.to_ascii_uppercase(), String::new()),
+        })
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+fn split_unescaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    let mut in_quotes = false;
+    for c in text.chars() {
+        let current = parts.last_mut().unwrap();
+        if escaped {
+            current.push('\\');
+            current.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            in_quotes = !in_quotes;
+            current.push(c);
+        } else if c == separator && !in_quotes {
+            parts.push(String::new());
+        } else {
+            current.push(c);
+        }
+    }
+    parts
+}
+
+/// Resolves the TEXT value escapes `\\`, `\;`, `\,` and `\n`.
+pub(crate) fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+///
+/// Formats an iCalendar/vCard DATE or DATE-TIME (`20240115`, `20240115T090000Z`) for display.
+/// Values that do not look like either are returned unchanged.
+pub(crate) fn format_datetime(value: &str, tzid: Option<&str>) -> String {
+    let value = value.trim();
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    if date.len() != 8 || !digits(date) {
+        return value.to_string();
+    }
+    let mut result = format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..8]);
+
+    if let Some(time) = time {
+        let (clock, utc) = match time.strip_suffix('Z') {
+            Some(clock) => (clock, true),
+            None => (time, false),
+        };
+        if clock.len() < 4 || !digits(clock) {
+            return value.to_string();
+        }
+        result.push_str(&format!(" {}:{}", &clock[..2], &clock[2..4]));
+        if utc {
+            result.push_str(" UTC");
+        } else if let Some(tzid) = tzid {
+            result.push_str(&format!(" ({})", tzid));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_unfold_and_unescape() {
+        let properties = parse(
+            "DESCRIPTION:Agenda:\\n1. Budget\\, Q3\r\n  review\r\nSUMMARY;LANGUAGE=en:Sync\r\n",
+        );
+
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties[0].text(), "Agenda:\n1. Budget, Q3 review");
+        assert_eq!(properties[1].param("language"), Some("en"));
+    }
+
+    #[test]
+    fn test_quoted_printable_soft_breaks() {
+        let properties = parse(
+            "NOTE;ENCODING=QUOTED-PRINTABLE;CHARSET=UTF-8:Caf=C3=A9 au lait=\r\n, s'il vous pla=\r\n=C3=AEt\r\n",
+        );
+
+        assert_eq!(properties[0].text(), "Café au lait, s'il vous plaît");
+    }
+
+    #[test]
+    fn test_format_datetime() {
+        assert_eq!(
+            format_datetime("20240115T090000Z", None),
+            "2024-01-15 09:00 UTC"
+        );
+        assert_eq!(
+            format_datetime("20240115T090000", Some("Europe/Berlin")),
+            "2024-01-15 09:00 (Europe/Berlin)"
+        );
+        assert_eq!(format_datetime("20240115", None), "2024-01-15");
+    }
+}
@@ -0,0 +1,127 @@
+//! Date formats of document metadata, shared by the PDF and Office Open XML extractors.
+
+use time::{Date, Month, OffsetDateTime, Time, UtcOffset};
+
+fn datetime(
+    (year, month, day): (i32, u8, u8),
+    (hour, minute, second): (u8, u8, u8),
+    offset_seconds: i32,
+) -> Option<OffsetDateTime> {
+    let date = Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()?;
+    let time = Time::from_hms(hour, minute, second).ok()?;
+    let offset = UtcOffset::from_whole_seconds(offset_seconds).ok()?;
+    Some(date.with_time(time).assume_offset(offset))
+}
+
+///
+/// Reads fixed-width numbers, each preceded by its separator, stopping early at the end of the
+/// input or at anything unexpected. Missing numbers keep their default.
+fn numbers<'a, const N: usize>(
+    text: &'a str,
+    fields: [(&str, usize); N],
+    defaults: [u32; N],
+) -> ([u32; N], &'a str) {
+    let mut values = defaults;
+    let mut rest = text;
+    for (value, (separator, width)) in values.iter_mut().zip(fields) {
+        let Some(digits) = rest
+            .strip_prefix(separator)
+            .and_then(|field| field.get(..width))
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+        else {
+            break;
+        };
+        *value = digits.parse().unwrap_or(*value);
+        rest = &rest[separator.len() + width..];
+    }
+    (values, rest)
+}
+
+/// Parses a `Z`, `+HH'mm'` or `+HH:mm` UTC offset into seconds (missing means UTC).
+fn offset(text: &str) -> i32 {
+    let sign = match text.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return 0,
+    };
+    let digits = text[1..].replace(['\'', ':'], "");
+    let ([hours, minutes], _) = numbers(&digits, [("", 2), ("", 2)], [0, 0]);
+    sign * (hours as i32 * 3600 + minutes as i32 * 60)
+}
+
+fn starts_with_year(text: &str) -> bool {
+    text.get(..4)
+        .is_some_and(|year| year.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Parses a PDF date string such as `D:20090324113315-06'00'`. Everything after the year is
+/// optional.
+pub(crate) fn parse_pdf_date(text: &str) -> Option<OffsetDateTime> {
+    let text = text.trim();
+    let text = text.strip_prefix("D:").unwrap_or(text);
+    if !starts_with_year(text) {
+        return None;
+    }
+    let ([year, month, day, hour, minute, second], rest) = numbers(
+        text,
+        [("", 4), ("", 2), ("", 2), ("", 2), ("", 2), ("", 2)],
+        [0, 1, 1, 0, 0, 0],
+    );
+    datetime(
+        (year as i32, month as u8, day as u8),
+        (hour as u8, minute as u8, second as u8),
+        offset(rest),
+    )
+}
+
+/// Parses a W3CDTF (ISO 8601) date, as used by XMP and OOXML core properties, such as `2024-08-30T16:58:25+00:00` or `2024-08`.
+pub(crate) fn parse_iso_date(text: &str) -> Option<OffsetDateTime> {
+    let text = text.trim();
+    if !starts_with_year(text) {
+        return None;
+    }
+    let ([year, month, day, hour, minute, second], rest) = numbers(
+        text,
+        [("", 4), ("-", 2), ("-", 2), ("T", 2), (":", 2), (":", 2)],
+        [0, 1, 1, 0, 0, 0],
+    );
+    // fractional seconds are dropped
+    let rest = rest.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    datetime(
+        (year as i32, month as u8, day as u8),
+        (hour as u8, minute as u8, second as u8),
+        offset(rest),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_parse_dates() {
+        assert_eq!(
+            parse_pdf_date("D:20090324113315-06'00'"),
+            Some(datetime!(2009-03-24 11:33:15 -6))
+        );
+        assert_eq!(
+            parse_pdf_date("D:20240830165825Z"),
+            Some(datetime!(2024-08-30 16:58:25 UTC))
+        );
+        assert_eq!(
+            parse_pdf_date("D:2001"),
+            Some(datetime!(2001-01-01 0:00 UTC))
+        );
+        assert_eq!(
+            parse_pdf_date("D:200903241133-06'00'"),
+            Some(datetime!(2009-03-24 11:33 -6))
+        );
+        assert_eq!(parse_pdf_date("yesterday"), None);
+        assert_eq!(
+            parse_iso_date("2024-08-30T16:58:25.123+02:00"),
+            Some(datetime!(2024-08-30 16:58:25 +2))
+        );
+    }
+}
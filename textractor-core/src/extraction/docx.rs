@@ -0,0 +1,970 @@
+use std::io::Cursor;
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+use xml::reader::{EventReader, XmlEvent};
+use zip::ZipArchive;
+
+use super::boilerplate::{self, BoilerplateLine};
+use super::ooxml::{read_part, relationships, OoxmlMetadata, Relationship};
+use super::Extract;
+use math::MathReader;
+use numbering::{ListCounters, Numbering};
+use styles::Styles;
+use tables::TableBuilder;
+
+mod math;
+mod numbering;
+mod parts;
+mod styles;
+mod symbols;
+mod tables;
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use parts::{DocxPart, DocxPartKind};
+pub use tables::{DocxCell, DocxTable};
+
+pub struct DocxExtractor;
+
+/// How tracked changes (insertions and deletions not yet accepted or rejected) are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocxTrackedChanges {
+    /// The text as it would read with all changes accepted.
+    #[default]
+    Accepted,
+    /// The text as it was before the changes.
+    Original,
+    /// Both, with insertions marked as `{++inserted++}` and deletions as `{--deleted--}`
+    /// (CriticMarkup).
+    Markup,
+}
+
+/// How equations (Office Math) are written in the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocxMath {
+    /// LaTeX, between `$` inside paragraphs and `$$` for display equations: `$\frac{a}{b}$`.
+    #[default]
+    Latex,
+    /// Linear Unicode text, close to the UnicodeMath linear format of Word: `a/b`, `x²`, `√(x+1)`.
+    Unicode,
+}
+
+/// Options for DOCX extraction.
+#[derive(Debug, Clone, Default)]
+pub struct DocxOptions {
+    /// How tracked changes are rendered, in the body and in the other parts.
+    pub tracked_changes: DocxTrackedChanges,
+    /// How equations are written, in the body and in the other parts.
+    pub math: DocxMath,
+    /// Read the page headers, and append them to the text.
+    pub include_headers: bool,
+    /// Read the page footers, and append them to the text.
+    pub include_footers: bool,
+    /// Read footnotes, and append them to the text labelled by number.
+    pub include_footnotes: bool,
+    /// Read endnotes, and append them to the text labelled by number.
+    pub include_endnotes: bool,
+    /// Read comments, and append them to the text labelled by author and date.
+    pub include_comments: bool,
+    /// Mark headings (paragraphs with an outline level) as Markdown-style headings (`## Title`).
+    pub insert_headings: bool,
+    ///
+    /// Remove running headers, footers and page numbers typed into the body (as in documents
+    /// converted from PDF): lines repeated at the top or bottom of many pages. The removed lines
+    /// are reported in [`DocxDocument::boilerplate`].
+    pub remove_boilerplate: bool,
+}
+
+/// The result of a DOCX extraction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocxDocument {
+    ///
+    /// The text of the body, split into pages at page and section breaks, and where Word broke
+    /// pages when it last laid out the document. Paragraphs and tables are separated by blank
+    /// lines, with one line per table row and cells separated by tabs.
+    pub pages: Vec<String>,
+    /// The tables of the body, in document order. Nested tables are found in their cells.
+    pub tables: Vec<DocxTable>,
+    /// The headings of the body, in document order.
+    pub headings: Vec<DocxHeading>,
+    /// Headers, footers, footnotes, endnotes and comments, as selected by the options.
+    pub parts: Vec<DocxPart>,
+    /// The document properties: title, author, dates, statistics...
+    pub metadata: OoxmlMetadata,
+    /// Lines removed from the pages, when [`DocxOptions::remove_boilerplate`] is set.
+    pub boilerplate: Vec<BoilerplateLine>,
+}
+
+/// A heading of a DOCX document, from the outline level of its paragraph or paragraph style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocxHeading {
+    /// 1-based level, 1 for "Heading 1".
+    pub level: u32,
+    /// The heading text, with its list number if it has one.
+    pub text: String,
+    /// 1-based number of the page the heading is on.
+    pub page: u32,
+}
+
+impl DocxDocument {
+    ///
+    /// The text of all pages, separated by form feeds, followed by the labelled text of the other
+    /// parts.
+    pub fn text(&self) -> String {
+        let mut text = self.pages.join("\x0c");
+        for part in &self.parts {
+            text.push('\n');
+            text.push_str(&part.labelled_text());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+const WORDPROCESSINGML: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const MARKUP_COMPATIBILITY: &str = "http://schemas.openxmlformats.org/markup-compatibility/2006";
+
+/// Whether the element is `w:{local}`.
+fn is_w(name: &OwnedName, local: &str) -> bool {
+    name.local_name == local && name.namespace.as_deref() == Some(WORDPROCESSINGML)
+}
+
+/// The value of the `w:{local}` attribute.
+fn w_attribute<'a>(attributes: &'a [OwnedAttribute], local: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attribute| is_w(&attribute.name, local))
+        .map(|attribute| attribute.value.as_str())
+}
+
+/// Whether an on/off property (`<w:b/>`, `<w:b w:val="false"/>`...) is on.
+fn is_on(attributes: &[OwnedAttribute]) -> bool {
+    !matches!(w_attribute(attributes, "val"), Some("false" | "0" | "off"))
+}
+
+/// The path of the main document part, from the package relationships.
+fn main_part(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<String> {
+    Ok(relationships(archive, "")?
+        .into_iter()
+        .find(|relationship| relationship.kind == "officeDocument")
+        .map_or("word/document.xml".to_string(), |relationship| {
+            relationship.target
+        }))
+}
+
+/// Styles and list definitions, shared by the parts of a document.
+#[derive(Debug, Default)]
+struct Definitions {
+    styles: Styles,
+    numbering: Numbering,
+}
+
+/// A paragraph being read.
+#[derive(Default)]
+struct Paragraph {
+    text: String,
+    style: Option<String>,
+    num_id: Option<String>,
+    level: Option<usize>,
+    outline_level: Option<u32>,
+    /// Whether the list marker and heading level were resolved, which happens once the
+    /// properties of the paragraph were read.
+    resolved: bool,
+    /// The list marker, until it is written before the first text of the paragraph.
+    marker: Option<String>,
+    /// The 0-based outline level, until the paragraph is recorded as a heading.
+    heading: Option<u32>,
+}
+
+impl Paragraph {
+    fn resolve(&mut self, definitions: &Definitions, counters: &mut ListCounters) {
+        if std::mem::replace(&mut self.resolved, true) {
+            return;
+        }
+        let styles = &definitions.styles;
+        let style = self.style.as_deref();
+        self.heading = self
+            .outline_level
+            .or_else(|| styles.outline_level(style))
+            .filter(|level| *level < 9);
+        let (num_id, level) = styles.numbering(style);
+        // `w:numId w:val="0"` removes the numbering given by the style
+        self.marker = self
+            .num_id
+            .clone()
+            .or(num_id)
+            .filter(|id| id != "0")
+            .and_then(|id| {
+                let level = self.level.or(level).unwrap_or(0);
+                counters.next_marker(&definitions.numbering, &id, level)
+            });
+    }
+}
+
+/// The text and structure read from a part of a document.
+struct Body {
+    pages: Vec<String>,
+    tables: Vec<DocxTable>,
+    headings: Vec<DocxHeading>,
+}
+
+/// Text of the document body, assembled page by page.
+#[derive(Default)]
+struct BodyText {
+    tracked_changes: DocxTrackedChanges,
+    math_format: DocxMath,
+    insert_headings: bool,
+    definitions: Rc<Definitions>,
+    counters: ListCounters,
+    pages: Vec<String>,
+    page: String,
+    /// The paragraphs being read; text boxes nest paragraphs inside paragraphs.
+    paragraphs: Vec<Paragraph>,
+    headings: Vec<DocxHeading>,
+    in_text: bool,
+    /// Depth inside tracked insertions (`w:ins`, `w:moveTo`) and deletions (`w:del`,
+    /// `w:moveFrom`).
+    inserted: usize,
+    deleted: usize,
+    /// The closing CriticMarkup of the change being written, in [`DocxTrackedChanges::Markup`].
+    open_mark: Option<&'static str>,
+    /// For each content control being read, whether it shows placeholder text.
+    content_controls: Vec<bool>,
+    in_paragraph_properties: bool,
+    /// Inside `w:pPrChange`, the properties of the paragraph before a tracked change.
+    in_property_change: bool,
+    /// A section break that starts a new page follows the current paragraph.
+    section_break: bool,
+    /// Depth inside `mc:Fallback`, which repeats the content of `mc:Choice` for older readers.
+    fallback: usize,
+    /// The equation being read (`m:oMath`).
+    math: Option<MathReader>,
+    /// The number of equations read so far in the display equation (`m:oMathPara`) being read.
+    display_math: Option<usize>,
+    /// The tables being read; tables nest inside table cells.
+    open_tables: Vec<TableBuilder>,
+    tables: Vec<DocxTable>,
+}
+
+impl BodyText {
+    fn new(options: &DocxOptions, definitions: &Rc<Definitions>) -> BodyText {
+        BodyText {
+            tracked_changes: options.tracked_changes,
+            math_format: options.math,
+            insert_headings: options.insert_headings,
+            definitions: definitions.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn close_mark(&mut self) {
+        if let (Some(mark), Some(paragraph)) = (self.open_mark.take(), self.paragraphs.last_mut()) {
+            paragraph.text.push_str(mark);
+        }
+    }
+
+    /// Moves the finished lines of the current paragraph to the page, or to its table cell.
+    fn flush_paragraph(&mut self) {
+        self.close_mark();
+        let nested = self.paragraphs.len() > 1;
+        let Some(paragraph) = self.paragraphs.last_mut() else {
+            return;
+        };
+        paragraph.resolve(&self.definitions, &mut self.counters);
+        if paragraph.text.trim().is_empty() {
+            paragraph.text.clear();
+            return;
+        }
+        let mut text = paragraph.marker.take().unwrap_or_default();
+        text.push_str(paragraph.text.trim_end_matches('\n'));
+        paragraph.text.clear();
+        let heading = paragraph.heading.take();
+
+        let in_cell = self
+            .open_tables
+            .last_mut()
+            .is_some_and(|table| table.push_paragraph(&text));
+        if in_cell {
+            return;
+        }
+        if let Some(level) = heading.filter(|_| !nested) {
+            self.headings.push(DocxHeading {
+                level: level + 1,
+                text: text.trim().to_string(),
+                page: self.pages.len() as u32 + 1,
+            });
+            if self.insert_headings {
+                text = format!("{} {}", "#".repeat(level as usize + 1), text);
+            }
+        }
+        if !self.page.is_empty() {
+            self.page.push('\n');
+        }
+        self.page.push_str(&text);
+        self.page.push('\n');
+    }
+
+    fn end_table(&mut self) {
+        let Some(table) = self.open_tables.pop() else {
+            return;
+        };
+        let table = table.build();
+        match self.open_tables.last_mut() {
+            Some(outer) => outer.push_table(table),
+            None => {
+                let text = table.text();
+                if !text.is_empty() {
+                    if !self.page.is_empty() {
+                        self.page.push('\n');
+                    }
+                    self.page.push_str(&text);
+                }
+                self.tables.push(table);
+            }
+        }
+    }
+
+    /// Starts a new page, unless the current one is still empty.
+    fn page_break(&mut self) {
+        // breaks inside text boxes belong to the layout of the box, not of the body, and tables
+        // are kept whole on the page they start on
+        if self.paragraphs.len() > 1 || !self.open_tables.is_empty() {
+            return;
+        }
+        self.flush_paragraph();
+        if !self.page.is_empty() {
+            self.pages.push(std::mem::take(&mut self.page));
+        }
+    }
+
+    fn start(&mut self, name: &OwnedName, attributes: &[OwnedAttribute]) {
+        if name.local_name == "Fallback" && name.namespace.as_deref() == Some(MARKUP_COMPATIBILITY)
+        {
+            self.fallback += 1;
+        }
+        if self.fallback > 0 {
+            return;
+        }
+        if math::is_m(name, "oMath") && self.math.is_none() {
+            self.math = Some(MathReader::default());
+        }
+        if let Some(math) = &mut self.math {
+            math.start(name, attributes);
+            return;
+        }
+        if math::is_m(name, "oMathPara") {
+            self.display_math = Some(0);
+        }
+        if name.namespace.as_deref() != Some(WORDPROCESSINGML) {
+            return;
+        }
+        match name.local_name.as_str() {
+            "p" => self.paragraphs.push(Paragraph::default()),
+            "pPr" if !self.in_property_change => self.in_paragraph_properties = true,
+            "pPrChange" => self.in_property_change = true,
+            "pStyle" | "numId" | "ilvl" | "outlineLvl"
+                if self.in_paragraph_properties && !self.in_property_change =>
+            {
+                if let Some(paragraph) = self.paragraphs.last_mut() {
+                    let value = w_attribute(attributes, "val");
+                    match name.local_name.as_str() {
+                        "pStyle" => paragraph.style = value.map(str::to_string),
+                        "numId" => paragraph.num_id = value.map(str::to_string),
+                        "ilvl" => paragraph.level = value.and_then(|value| value.parse().ok()),
+                        _ => paragraph.outline_level = value.and_then(|value| value.parse().ok()),
+                    }
+                }
+            }
+            "pageBreakBefore" if self.in_paragraph_properties && is_on(attributes) => {
+                self.page_break()
+            }
+            "sectPr" if self.in_paragraph_properties => self.section_break = true,
+            // continuous sections (column layout changes...) stay on the same page
+            "type"
+                if self.section_break && w_attribute(attributes, "val") == Some("continuous") =>
+            {
+                self.section_break = false
+            }
+            "br" if w_attribute(attributes, "type") == Some("page") => self.page_break(),
+            "br" | "cr" => self.push_text("\n"),
+            // tab stop definitions are in the paragraph properties
+            "tab" | "ptab" if !self.in_paragraph_properties => self.push_text("\t"),
+            "noBreakHyphen" => self.push_text("-"),
+            "sym" => {
+                let font = w_attribute(attributes, "font");
+                if let Some(c) =
+                    w_attribute(attributes, "char").and_then(|code| symbols::symbol(font, code))
+                {
+                    self.push_text(c.encode_utf8(&mut [0; 4]));
+                }
+            }
+            "lastRenderedPageBreak" => self.page_break(),
+            "t" | "delText" => self.in_text = true,
+            "ins" | "moveTo" => self.inserted += 1,
+            "del" | "moveFrom" => self.deleted += 1,
+            "sdt" => self.content_controls.push(false),
+            "showingPlcHdr" if is_on(attributes) => {
+                if let Some(placeholder) = self.content_controls.last_mut() {
+                    *placeholder = true;
+                }
+            }
+            "tbl" => self.open_tables.push(TableBuilder::default()),
+            _ => {}
+        }
+        let Some(table) = self.open_tables.last_mut() else {
+            return;
+        };
+        let count = || {
+            w_attribute(attributes, "val")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(1)
+        };
+        match name.local_name.as_str() {
+            "tr" => table.start_row(),
+            "tc" => table.start_cell(),
+            "gridBefore" => table.set_grid_before(count()),
+            "gridSpan" => table.set_column_span(count()),
+            "vMerge" => table.set_vertical_merge(w_attribute(attributes, "val")),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &OwnedName) {
+        if self.fallback > 0 {
+            if name.local_name == "Fallback"
+                && name.namespace.as_deref() == Some(MARKUP_COMPATIBILITY)
+            {
+                self.fallback -= 1;
+            }
+            return;
+        }
+        if let Some(math) = &mut self.math {
+            if let Some(equation) = math.end() {
+                self.math = None;
+                self.push_equation(&equation);
+            }
+            return;
+        }
+        if math::is_m(name, "oMathPara") {
+            self.display_math = None;
+        }
+        if name.namespace.as_deref() != Some(WORDPROCESSINGML) {
+            return;
+        }
+        match name.local_name.as_str() {
+            "p" => {
+                let nested = self.paragraphs.len() > 1;
+                self.flush_paragraph();
+                self.paragraphs.pop();
+                if std::mem::take(&mut self.section_break) && !nested {
+                    self.page_break();
+                }
+            }
+            "pPr" if !self.in_property_change => self.in_paragraph_properties = false,
+            "pPrChange" => self.in_property_change = false,
+            "t" | "delText" => self.in_text = false,
+            "ins" | "moveTo" => {
+                self.inserted = self.inserted.saturating_sub(1);
+                self.close_mark();
+            }
+            "del" | "moveFrom" => {
+                self.deleted = self.deleted.saturating_sub(1);
+                self.close_mark();
+            }
+            "sdt" => {
+                self.content_controls.pop();
+            }
+            "tbl" => self.end_table(),
+            _ => {}
+        }
+    }
+
+    fn handle(&mut self, event: &XmlEvent) {
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => self.start(name, attributes),
+            XmlEvent::EndElement { name } => self.end(name),
+            XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => self.characters(text),
+            _ => {}
+        }
+    }
+
+    fn characters(&mut self, text: &str) {
+        if let Some(math) = &mut self.math {
+            math.characters(text);
+        } else if self.in_text {
+            self.push_text(text);
+        }
+    }
+
+    /// Adds an equation to the current paragraph, in the format of the options.
+    fn push_equation(&mut self, equation: &math::MathNode) {
+        let text = match self.math_format {
+            DocxMath::Latex => equation.latex(),
+            DocxMath::Unicode => equation.unicode(),
+        };
+        if text.trim().is_empty() {
+            return;
+        }
+        let (display, first) = match &mut self.display_math {
+            Some(count) => {
+                *count += 1;
+                (true, *count == 1)
+            }
+            None => (false, true),
+        };
+        if !first {
+            self.push_text("\n");
+        }
+        match (self.math_format, display) {
+            (DocxMath::Latex, true) => self.push_text(&format!("$${}$$", text)),
+            (DocxMath::Latex, false) => self.push_text(&format!("${}$", text)),
+            (DocxMath::Unicode, _) => self.push_text(&text),
+        }
+    }
+
+    /// Adds text of a run to the current paragraph.
+    fn push_text(&mut self, text: &str) {
+        // the placeholder of an empty content control ("Click here to enter text.")
+        if self.fallback > 0 || self.content_controls.contains(&true) {
+            return;
+        }
+        let mark = match (self.deleted > 0, self.inserted > 0) {
+            (true, _) => ("{--", "--}"),
+            (false, true) => ("{++", "++}"),
+            (false, false) => ("", ""),
+        };
+        let shown = match self.tracked_changes {
+            DocxTrackedChanges::Accepted => self.deleted == 0,
+            DocxTrackedChanges::Original => self.deleted > 0 || self.inserted == 0,
+            DocxTrackedChanges::Markup => true,
+        };
+        if !shown {
+            return;
+        }
+        if self.tracked_changes == DocxTrackedChanges::Markup && self.open_mark != Some(mark.1) {
+            self.close_mark();
+            if !mark.0.is_empty() {
+                self.open_mark = Some(mark.1);
+                if let Some(paragraph) = self.paragraphs.last_mut() {
+                    paragraph.text.push_str(mark.0);
+                }
+            }
+        }
+        if let Some(paragraph) = self.paragraphs.last_mut() {
+            paragraph.text.push_str(text);
+        }
+    }
+
+    fn finish(mut self) -> Body {
+        while !self.open_tables.is_empty() {
+            self.end_table();
+        }
+        self.page_break();
+        Body {
+            pages: self.pages,
+            tables: self.tables,
+            headings: self.headings,
+        }
+    }
+}
+
+/// Reads the text of the document body, split into pages, and its structure.
+fn read_body(xml: &str, options: &DocxOptions, definitions: &Rc<Definitions>) -> Result<Body> {
+    let mut body = BodyText::new(options, definitions);
+    for event in EventReader::new(xml.as_bytes()) {
+        body.handle(&event.context("Failed to parse XML")?);
+    }
+    Ok(body.finish())
+}
+
+/// Reads the styles and list definitions the document refers to.
+fn read_definitions(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    relationships: &[Relationship],
+) -> Result<Definitions> {
+    let mut definitions = Definitions::default();
+    for relationship in relationships {
+        match relationship.kind.as_str() {
+            "styles" => {
+                if let Some(xml) = read_part(archive, &relationship.target)? {
+                    definitions.styles = Styles::parse(&xml)
+                        .with_context(|| format!("Failed to read {}", relationship.target))?;
+                }
+            }
+            "numbering" => {
+                if let Some(xml) = read_part(archive, &relationship.target)? {
+                    definitions.numbering = Numbering::parse(&xml)
+                        .with_context(|| format!("Failed to read {}", relationship.target))?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(definitions)
+}
+
+impl DocxExtractor {
+    /// Extracts the text of a DOCX document page by page.
+    pub fn extract_document(data: &[u8], options: &DocxOptions) -> Result<DocxDocument> {
+        let mut archive =
+            ZipArchive::new(Cursor::new(data)).context("Failed to read ZIP archive")?;
+        let name = main_part(&mut archive)?;
+        let xml = read_part(&mut archive, &name)?
+            .with_context(|| format!("Missing main document part {}", name))?;
+        let relationships = relationships(&mut archive, &name)?;
+        let definitions = Rc::new(read_definitions(&mut archive, &relationships)?);
+        let Body {
+            mut pages,
+            tables,
+            headings,
+        } = read_body(&xml, options, &definitions)?;
+        let parts = parts::read_parts(&mut archive, &relationships, options, &definitions)?;
+        // the document properties are optional, unreadable ones don't fail the extraction
+        let metadata = OoxmlMetadata::read_archive(&mut archive).unwrap_or_default();
+
+        let boilerplate = match options.remove_boilerplate {
+            true => boilerplate::remove_boilerplate(
+                &mut pages
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, page)| (i as u32 + 1, page))
+                    .collect::<Vec<_>>(),
+            ),
+            false => Vec::new(),
+        };
+        Ok(DocxDocument {
+            pages,
+            tables,
+            headings,
+            parts,
+            metadata,
+            boilerplate,
+        })
+    }
+}
+
+impl Extract for DocxExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        Ok(Self::extract_document(data, &DocxOptions::default())?.text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{docx, paragraph, NAMESPACES};
+    use super::*;
+    use crate::detection::ContentType;
+    use pretty_assertions::assert_eq;
+
+    const PAGE_BREAK: &str = r#"<w:p><w:r><w:br w:type="page"/></w:r></w:p>"#;
+
+    #[test]
+    fn test_extract_docx() {
+        let data = docx(
+            r#"<w:p><w:r><w:t>Hello </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>world</w:t></w:r></w:p>
+            <w:p/>
+            <w:p><w:r><w:t>Second paragraph</w:t></w:r><w:del><w:r><w:delText>gone</w:delText></w:r></w:del></w:p>"#,
+            &[],
+        );
+        assert_eq!(
+            ContentType::from(data.as_slice()),
+            ContentType::WordDocument
+        );
+
+        let text = DocxExtractor::extract(&data).unwrap();
+
+        assert_eq!(text, "Hello world\n\nSecond paragraph\n");
+    }
+
+    #[test]
+    fn test_runs_tabs_and_breaks() {
+        let data = docx(
+            r#"<w:p><w:pPr><w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs></w:pPr><w:r><w:t>Name:</w:t><w:tab/><w:t>Ada</w:t></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">A sentence in </w:t></w:r><w:r><w:rPr><w:i/></w:rPr><w:t>several</w:t></w:r><w:r><w:t xml:space="preserve"> runs</w:t></w:r><w:r><w:br/><w:t>next line</w:t></w:r></w:p>
+            <w:p><w:r><w:sym w:font="Symbol" w:char="F0B7"/><w:t xml:space="preserve"> </w:t><w:sym w:font="Symbol" w:char="F061"/><w:t xml:space="preserve"> </w:t><w:sym w:font="Wingdings" w:char="F0FC"/><w:t xml:space="preserve"> e</w:t><w:noBreakHyphen/><w:t>mail</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Before</w:t><w:br w:type="page"/><w:t>After</w:t></w:r></w:p>"#,
+            &[],
+        );
+
+        assert_eq!(
+            DocxExtractor::extract(&data).unwrap(),
+            "Name:\tAda\n\nA sentence in several runs\nnext line\n\n• α ✓ e-mail\n\nBefore\n\x0cAfter\n"
+        );
+    }
+
+    #[test]
+    fn test_lists_and_headings() {
+        let rels = r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+            <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+            <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>
+        </Relationships>"#;
+        let styles = format!(
+            r#"<w:styles {}>
+                <w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+                <w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:pPr><w:numPr><w:numId w:val="1"/></w:numPr></w:pPr></w:style>
+                <w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Heading1"/><w:pPr><w:numPr><w:ilvl w:val="1"/></w:numPr></w:pPr></w:style>
+                <w:style w:type="paragraph" w:styleId="Summary"><w:name w:val="Summary"/><w:pPr><w:outlineLvl w:val="2"/></w:pPr></w:style>
+            </w:styles>"#,
+            NAMESPACES
+        );
+        let numbering = format!(
+            r#"<w:numbering {}>
+                <w:abstractNum w:abstractNumId="0">
+                    <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1"/></w:lvl>
+                    <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1.%2"/></w:lvl>
+                </w:abstractNum>
+                <w:abstractNum w:abstractNumId="1">
+                    <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="lowerLetter"/><w:lvlText w:val="(%1)"/></w:lvl>
+                </w:abstractNum>
+                <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+                <w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+            </w:numbering>"#,
+            NAMESPACES
+        );
+        let styled = |style: &str, text: &str| {
+            format!(
+                r#"<w:p><w:pPr><w:pStyle w:val="{}"/></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#,
+                style, text
+            )
+        };
+        let item = |text: &str| {
+            format!(
+                r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="2"/></w:numPr></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#,
+                text
+            )
+        };
+        let body = [
+            styled("Heading1", "Definitions"),
+            styled("Heading2", "Terms"),
+            item("Agreement"),
+            item("Services"),
+            styled("Heading2", "Interpretation"),
+            // numbering removed from a heading
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/><w:numPr><w:numId w:val="0"/></w:numPr></w:pPr><w:r><w:t>Appendix</w:t></w:r></w:p>"#.to_string(),
+            styled("Summary", "Key points"),
+        ];
+        let data = docx(
+            &body.concat(),
+            &[
+                ("word/_rels/document.xml.rels", rels),
+                ("word/styles.xml", &styles),
+                ("word/numbering.xml", &numbering),
+            ],
+        );
+        let options = DocxOptions {
+            insert_headings: true,
+            ..Default::default()
+        };
+
+        let document = DocxExtractor::extract_document(&data, &options).unwrap();
+
+        assert_eq!(
+            document.text(),
+            "# 1 Definitions\n\n## 1.1 Terms\n\n(a) Agreement\n\n(b) Services\n\n\
+             ## 1.2 Interpretation\n\n# Appendix\n\n### Key points\n"
+        );
+        assert_eq!(
+            document.headings[1],
+            DocxHeading {
+                level: 2,
+                text: "1.1 Terms".to_string(),
+                page: 1,
+            }
+        );
+        assert_eq!(document.headings.len(), 5);
+    }
+
+    #[test]
+    fn test_pages() {
+        let body = [
+            paragraph("First page"),
+            PAGE_BREAK.to_string(),
+            paragraph("Second page"),
+            // a section starting on a new page
+            r#"<w:p><w:pPr><w:sectPr><w:type w:val="nextPage"/></w:sectPr></w:pPr><w:r><w:t>End of section</w:t></w:r></w:p>"#.to_string(),
+            r#"<w:p><w:r><w:lastRenderedPageBreak/><w:t>Third page</w:t></w:r></w:p>"#.to_string(),
+            r#"<w:p><w:pPr><w:sectPr><w:type w:val="continuous"/></w:sectPr></w:pPr><w:r><w:t>Same page</w:t></w:r></w:p>"#.to_string(),
+            r#"<w:p><w:pPr><w:pageBreakBefore/></w:pPr><w:r><w:t>Fourth page</w:t></w:r></w:p>"#.to_string(),
+        ];
+        let data = docx(&body.concat(), &[]);
+
+        let document = DocxExtractor::extract_document(&data, &DocxOptions::default()).unwrap();
+
+        assert_eq!(
+            document.pages,
+            vec![
+                "First page\n",
+                "Second page\n\nEnd of section\n",
+                "Third page\n\nSame page\n",
+                "Fourth page\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alternate_content() {
+        let data = docx(
+            r#"<w:p><w:r><mc:AlternateContent>
+                <mc:Choice Requires="wps"><w:drawing><w:txbxContent><w:p><w:r><w:t>Boxed</w:t></w:r></w:p></w:txbxContent></w:drawing></mc:Choice>
+                <mc:Fallback><w:pict><w:txbxContent><w:p><w:r><w:t>Boxed</w:t></w:r></w:p></w:txbxContent></w:pict></mc:Fallback>
+            </mc:AlternateContent></w:r><w:r><w:t>Body</w:t></w:r></w:p>"#,
+            &[],
+        );
+
+        assert_eq!(DocxExtractor::extract(&data).unwrap(), "Boxed\n\nBody\n");
+    }
+
+    #[test]
+    fn test_links_and_content_controls() {
+        let data = docx(
+            r#"<w:sdt><w:sdtPr><w:docPartObj><w:docPartGallery w:val="Table of Contents"/></w:docPartObj></w:sdtPr><w:sdtContent>
+                <w:p><w:hyperlink w:anchor="_Toc1"><w:r><w:t>Introduction</w:t></w:r><w:r><w:t xml:space="preserve"> 1</w:t></w:r></w:hyperlink></w:p>
+            </w:sdtContent></w:sdt>
+            <w:p><w:r><w:t xml:space="preserve">Visit </w:t></w:r><w:hyperlink r:id="rId9"><w:r><w:t>our website</w:t></w:r></w:hyperlink></w:p>
+            <w:p><w:r><w:t xml:space="preserve">Client: </w:t></w:r><w:sdt><w:sdtContent><w:r><w:t>ACME Corp</w:t></w:r></w:sdtContent></w:sdt></w:p>
+            <w:p><w:r><w:t xml:space="preserve">Date: </w:t></w:r><w:sdt><w:sdtPr><w:showingPlcHdr/></w:sdtPr><w:sdtContent><w:r><w:t>Click here to enter a date.</w:t></w:r></w:sdtContent></w:sdt></w:p>"#,
+            &[],
+        );
+
+        assert_eq!(
+            DocxExtractor::extract(&data).unwrap(),
+            "Introduction 1\n\nVisit our website\n\nClient: ACME Corp\n\nDate: \n"
+        );
+    }
+
+    #[test]
+    fn test_tracked_changes() {
+        let data = docx(
+            r#"<w:p><w:r><w:t xml:space="preserve">The fee is </w:t></w:r><w:del w:id="1" w:author="Ada"><w:r><w:delText>ten</w:delText></w:r></w:del><w:ins w:id="2" w:author="Ada"><w:r><w:t>twelve</w:t></w:r><w:r><w:t xml:space="preserve"> thousand</w:t></w:r></w:ins><w:r><w:t xml:space="preserve"> dollars.</w:t></w:r></w:p>
+            <w:p><w:pPr><w:rPr><w:ins w:id="3" w:author="Ada"/></w:rPr></w:pPr><w:ins w:id="4" w:author="Ada"><w:r><w:t>Added clause.</w:t></w:r></w:ins></w:p>"#,
+            &[],
+        );
+        let text = |tracked_changes| {
+            let options = DocxOptions {
+                tracked_changes,
+                ..Default::default()
+            };
+            DocxExtractor::extract_document(&data, &options)
+                .unwrap()
+                .text()
+        };
+
+        assert_eq!(
+            text(DocxTrackedChanges::Accepted),
+            "The fee is twelve thousand dollars.\n\nAdded clause.\n"
+        );
+        assert_eq!(
+            text(DocxTrackedChanges::Original),
+            "The fee is ten dollars.\n"
+        );
+        assert_eq!(
+            text(DocxTrackedChanges::Markup),
+            "The fee is {--ten--}{++twelve thousand++} dollars.\n\n{++Added clause.++}\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_boilerplate() {
+        let body: Vec<String> = ["Scope", "Terms", "Payment", "Signatures"]
+            .iter()
+            .enumerate()
+            .map(|(i, section)| {
+                format!(
+                    "{}{}{}{}",
+                    paragraph("Master Services Agreement"),
+                    paragraph(section),
+                    paragraph(&format!("Page {} of 4", i + 1)),
+                    PAGE_BREAK
+                )
+            })
+            .collect();
+        let data = docx(&body.concat(), &[]);
+        let options = DocxOptions {
+            remove_boilerplate: true,
+            ..Default::default()
+        };
+
+        let document = DocxExtractor::extract_document(&data, &options).unwrap();
+
+        assert_eq!(
+            document.text(),
+            "Scope\n\x0cTerms\n\x0cPayment\n\x0cSignatures\n"
+        );
+        assert_eq!(document.boilerplate.len(), 8);
+        assert_eq!(
+            document.boilerplate[7],
+            BoilerplateLine {
+                page: 4,
+                text: "Page 4 of 4".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_math() {
+        let r = |text: &str| format!("<m:r><m:t>{}</m:t></m:r>", text);
+        let quadratic = format!(
+            "<m:oMath>{}<m:f><m:num>{}<m:rad><m:radPr><m:degHide m:val=\"1\"/></m:radPr><m:deg/><m:e><m:sSup><m:e>{}</m:e><m:sup>{}</m:sup></m:sSup>{}</m:e></m:rad></m:num><m:den>{}</m:den></m:f></m:oMath>",
+            r("x="), r("-b±"), r("b"), r("2"), r("-4ac"), r("2a")
+        );
+        let sine = format!(
+            "<m:oMath><m:func><m:fName>{}</m:fName><m:e>{}</m:e></m:func></m:oMath>",
+            r("sin"),
+            r("θ")
+        );
+        let sum = format!(
+            "<m:oMath><m:nary><m:naryPr><m:chr m:val=\"∑\"/></m:naryPr><m:sub>{}</m:sub><m:sup>{}</m:sup><m:e><m:sSub><m:e>{}</m:e><m:sub>{}</m:sub></m:sSub></m:e></m:nary></m:oMath>",
+            r("i=1"), r("n"), r("x"), r("i")
+        );
+        let cells =
+            |a: &str, b: &str| format!("<m:mr><m:e>{}</m:e><m:e>{}</m:e></m:mr>", r(a), r(b));
+        let matrix = format!(
+            "<m:oMath>{}<m:d><m:dPr><m:begChr m:val=\"[\"/><m:endChr m:val=\"]\"/></m:dPr><m:e><m:m>{}{}</m:m></m:e></m:d></m:oMath>",
+            r("A="), cells("a", "b"), cells("c", "d")
+        );
+        let body = format!(
+            r#"<w:p><w:r><w:t xml:space="preserve">Roots: </w:t></w:r>{}<w:r><w:t xml:space="preserve"> and </w:t></w:r>{}</w:p><w:p><m:oMathPara>{}{}</m:oMathPara></w:p>"#,
+            quadratic, sine, sum, matrix
+        );
+        let data = docx(&body, &[]);
+
+        let latex = DocxExtractor::extract_document(&data, &DocxOptions::default()).unwrap();
+        let unicode = DocxExtractor::extract_document(
+            &data,
+            &DocxOptions {
+                math: DocxMath::Unicode,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            latex.text(),
+            "Roots: $x=\\frac{-b\\pm\\sqrt{b^{2}-4ac}}{2a}$ and $\\sin\\theta$\n\n$$\\sum_{i=1}^{n} x_{i}$$\n$$A=\\left[\\begin{matrix}a & b \\\\ c & d\\end{matrix}\\right]$$\n"
+        );
+        assert_eq!(
+            unicode.text(),
+            "Roots: x=(-b±√(b²-4ac))/2a and sin θ\n\n∑ᵢ₌₁ⁿ xᵢ\nA=[■(a&b@c&d)]\n"
+        );
+    }
+
+    #[test]
+    fn test_deeply_nested_math() {
+        let fraction = "<m:f><m:num><m:r><m:t>1</m:t></m:r></m:num><m:den><m:r><m:t>2</m:t></m:r></m:den></m:f>";
+        let body = format!(
+            "<w:p><m:oMath>{}<m:r><m:t>x+</m:t></m:r>{}{}</m:oMath></w:p>",
+            "<m:e>".repeat(5000),
+            fraction,
+            "</m:e>".repeat(5000)
+        );
+
+        let text = DocxExtractor::extract(&docx(&body, &[])).unwrap();
+
+        assert_eq!(text, "$x+12$\n");
+    }
+}
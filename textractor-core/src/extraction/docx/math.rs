@@ -0,0 +1,644 @@
+//! Office Math (OMML) equations, written as LaTeX or as linear Unicode text (UnicodeMath).
+
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+
+pub(crate) const MATH: &str = "http://schemas.openxmlformats.org/officeDocument/2006/math";
+
+// equations are written recursively, deeper elements are flattened to their text
+const MAX_DEPTH: usize = 64;
+
+/// Whether the element is `m:{local}`.
+pub(crate) fn is_m(name: &OwnedName, local: &str) -> bool {
+    name.local_name == local && name.namespace.as_deref() == Some(MATH)
+}
+
+/// An element of an equation.
+#[derive(Debug, Default)]
+pub(crate) struct MathNode {
+    /// The local name of `m:` elements, empty for elements of other namespaces.
+    name: String,
+    /// The `m:val` attribute.
+    value: Option<String>,
+    /// The text of `m:t` elements.
+    text: String,
+    children: Vec<MathNode>,
+}
+
+impl MathNode {
+    fn child(&self, name: &str) -> Option<&MathNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a MathNode> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// A property of the element, from its properties element (`m:fPr` for `m:f`...).
+    fn property(&self, name: &str) -> Option<&str> {
+        let properties = self.child(&format!("{}Pr", self.name))?;
+        properties.child(name)?.value.as_deref()
+    }
+
+    /// Whether an on/off property is on.
+    fn is_on(&self, name: &str) -> bool {
+        self.property(name)
+            .is_some_and(|value| !matches!(value, "0" | "off" | "false"))
+    }
+
+    fn text(&self) -> String {
+        self.children("t").map(|t| t.text.as_str()).collect()
+    }
+}
+
+/// Builds the tree of an `m:oMath` element from the events of its content.
+#[derive(Debug, Default)]
+pub(crate) struct MathReader {
+    stack: Vec<MathNode>,
+    /// Whether each element nested below [`MAX_DEPTH`] is an `m:t`. Their text goes to a
+    /// single run.
+    flattened: Vec<bool>,
+}
+
+impl MathReader {
+    pub fn start(&mut self, name: &OwnedName, attributes: &[OwnedAttribute]) {
+        let math = name.namespace.as_deref() == Some(MATH);
+        if self.stack.len() >= MAX_DEPTH || !self.flattened.is_empty() {
+            if self.flattened.is_empty() {
+                let t = MathNode {
+                    name: "t".to_string(),
+                    ..Default::default()
+                };
+                self.stack.push(MathNode {
+                    name: "r".to_string(),
+                    children: vec![t],
+                    ..Default::default()
+                });
+            }
+            self.flattened.push(math && name.local_name == "t");
+            return;
+        }
+        self.stack.push(MathNode {
+            name: if math {
+                name.local_name.clone()
+            } else {
+                String::new()
+            },
+            value: attributes
+                .iter()
+                .find(|attribute| {
+                    attribute.name.local_name == "val"
+                        && attribute.name.namespace.as_deref() == Some(MATH)
+                })
+                .map(|attribute| attribute.value.clone()),
+            ..Default::default()
+        });
+    }
+
+    pub fn characters(&mut self, text: &str) {
+        if !self.flattened.is_empty() {
+            if self.flattened.last() == Some(&true) {
+                if let Some(t) = self
+                    .stack
+                    .last_mut()
+                    .and_then(|run| run.children.first_mut())
+                {
+                    t.text.push_str(text);
+                }
+            }
+            return;
+        }
+        if let Some(node) = self.stack.last_mut().filter(|node| node.name == "t") {
+            node.text.push_str(text);
+        }
+    }
+
+    /// Ends the current element, returning the equation when it was the `m:oMath` element.
+    pub fn end(&mut self) -> Option<MathNode> {
+        // the flattened run ends with the element it replaces
+        if self.flattened.pop().is_some() && !self.flattened.is_empty() {
+            return None;
+        }
+        let node = self.stack.pop()?;
+        match self.stack.last_mut() {
+            Some(parent) => {
+                parent.children.push(node);
+                None
+            }
+            None => Some(node),
+        }
+    }
+}
+
+/// The LaTeX commands of characters.
+const LATEX_SYMBOLS: [(char, &str); 84] = [
+    ('α', "\\alpha"),
+    ('β', "\\beta"),
+    ('γ', "\\gamma"),
+    ('δ', "\\delta"),
+    ('ε', "\\varepsilon"),
+    ('ϵ', "\\epsilon"),
+    ('ζ', "\\zeta"),
+    ('η', "\\eta"),
+    ('θ', "\\theta"),
+    ('ϑ', "\\vartheta"),
+    ('ι', "\\iota"),
+    ('κ', "\\kappa"),
+    ('λ', "\\lambda"),
+    ('μ', "\\mu"),
+    ('ν', "\\nu"),
+    ('ξ', "\\xi"),
+    ('π', "\\pi"),
+    ('ρ', "\\rho"),
+    ('σ', "\\sigma"),
+    ('ς', "\\varsigma"),
+    ('τ', "\\tau"),
+    ('υ', "\\upsilon"),
+    ('φ', "\\varphi"),
+    ('ϕ', "\\phi"),
+    ('χ', "\\chi"),
+    ('ψ', "\\psi"),
+    ('ω', "\\omega"),
+    ('Γ', "\\Gamma"),
+    ('Δ', "\\Delta"),
+    ('Θ', "\\Theta"),
+    ('Λ', "\\Lambda"),
+    ('Ξ', "\\Xi"),
+    ('Π', "\\Pi"),
+    ('Σ', "\\Sigma"),
+    ('Υ', "\\Upsilon"),
+    ('Φ', "\\Phi"),
+    ('Ψ', "\\Psi"),
+    ('Ω', "\\Omega"),
+    ('∞', "\\infty"),
+    ('±', "\\pm"),
+    ('∓', "\\mp"),
+    ('×', "\\times"),
+    ('÷', "\\div"),
+    ('·', "\\cdot"),
+    ('⋅', "\\cdot"),
+    ('∙', "\\cdot"),
+    ('−', "-"),
+    ('≤', "\\leq"),
+    ('≥', "\\geq"),
+    ('≠', "\\neq"),
+    ('≈', "\\approx"),
+    ('≡', "\\equiv"),
+    ('∼', "\\sim"),
+    ('∝', "\\propto"),
+    ('→', "\\rightarrow"),
+    ('←', "\\leftarrow"),
+    ('↔', "\\leftrightarrow"),
+    ('⇒', "\\Rightarrow"),
+    ('⇐', "\\Leftarrow"),
+    ('⇔', "\\Leftrightarrow"),
+    ('∈', "\\in"),
+    ('∉', "\\notin"),
+    ('∋', "\\ni"),
+    ('⊂', "\\subset"),
+    ('⊃', "\\supset"),
+    ('⊆', "\\subseteq"),
+    ('⊇', "\\supseteq"),
+    ('∪', "\\cup"),
+    ('∩', "\\cap"),
+    ('∅', "\\emptyset"),
+    ('∀', "\\forall"),
+    ('∃', "\\exists"),
+    ('¬', "\\neg"),
+    ('∧', "\\wedge"),
+    ('∨', "\\vee"),
+    ('∂', "\\partial"),
+    ('∇', "\\nabla"),
+    ('…', "\\ldots"),
+    ('⋯', "\\cdots"),
+    ('ℏ', "\\hbar"),
+    ('ℓ', "\\ell"),
+    ('′', "'"),
+    ('″', "''"),
+    ('%', "\\%"),
+];
+
+/// The LaTeX commands of n-ary operators.
+const LATEX_OPERATORS: [(char, &str); 11] = [
+    ('∑', "\\sum"),
+    ('∏', "\\prod"),
+    ('∐', "\\coprod"),
+    ('∫', "\\int"),
+    ('∬', "\\iint"),
+    ('∭', "\\iiint"),
+    ('∮', "\\oint"),
+    ('⋃', "\\bigcup"),
+    ('⋂', "\\bigcap"),
+    ('⋁', "\\bigvee"),
+    ('⋀', "\\bigwedge"),
+];
+
+/// The LaTeX commands of accents, by combining character.
+const LATEX_ACCENTS: [(char, &str); 11] = [
+    ('\u{0300}', "\\grave"),
+    ('\u{0301}', "\\acute"),
+    ('\u{0302}', "\\hat"),
+    ('\u{0303}', "\\tilde"),
+    ('\u{0304}', "\\bar"),
+    ('\u{0305}', "\\bar"),
+    ('\u{0306}', "\\breve"),
+    ('\u{0307}', "\\dot"),
+    ('\u{0308}', "\\ddot"),
+    ('\u{030C}', "\\check"),
+    ('\u{20D7}', "\\vec"),
+];
+
+/// Functions with a LaTeX command of their own, e.g. `\sin`.
+const LATEX_FUNCTIONS: [&str; 24] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "coth", "log", "ln", "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "arg",
+];
+
+fn lookup<T: Copy>(table: &[(char, T)], c: char) -> Option<T> {
+    table
+        .iter()
+        .find(|(key, _)| *key == c)
+        .map(|(_, value)| *value)
+}
+
+/// Text of a run, with symbols replaced by LaTeX commands.
+fn latex_text(text: &str) -> String {
+    let mut latex = String::new();
+    let mut command = false;
+    for c in text.chars() {
+        // a command is ended by anything but a letter
+        if command && c.is_alphabetic() {
+            latex.push(' ');
+        }
+        match lookup(&LATEX_SYMBOLS, c) {
+            Some(symbol) => {
+                latex.push_str(symbol);
+                command = symbol.starts_with('\\') && symbol.ends_with(char::is_alphabetic);
+            }
+            None => {
+                if matches!(c, '{' | '}' | '#' | '&' | '_') {
+                    latex.push('\\');
+                }
+                latex.push(c);
+                command = false;
+            }
+        }
+    }
+    latex
+}
+
+/// Braces around LaTeX longer than a single character.
+fn group(latex: &str) -> String {
+    match latex.chars().count() {
+        1 => latex.to_string(),
+        _ => format!("{{{}}}", latex),
+    }
+}
+
+fn latex_delimiter(delimiter: &str) -> String {
+    match delimiter {
+        "" => ".".to_string(),
+        "{" => "\\{".to_string(),
+        "}" => "\\}".to_string(),
+        "⟨" | "〈" => "\\langle".to_string(),
+        "⟩" | "〉" => "\\rangle".to_string(),
+        "‖" => "\\|".to_string(),
+        "⌈" => "\\lceil".to_string(),
+        "⌉" => "\\rceil".to_string(),
+        "⌊" => "\\lfloor".to_string(),
+        "⌋" => "\\rfloor".to_string(),
+        delimiter => delimiter.to_string(),
+    }
+}
+
+fn latex_argument(node: &MathNode, name: &str) -> String {
+    node.child(name).map(latex).unwrap_or_default()
+}
+
+/// Writes an element as LaTeX.
+fn latex(node: &MathNode) -> String {
+    let argument = |name| latex_argument(node, name);
+    match node.name.as_str() {
+        "r" => match node
+            .child("rPr")
+            .is_some_and(|rpr| rpr.child("nor").is_some())
+        {
+            true => format!("\\text{{{}}}", node.text()),
+            false => latex_text(&node.text()),
+        },
+        "f" => {
+            let (numerator, denominator) = (argument("num"), argument("den"));
+            match node.property("type") {
+                Some("lin") | Some("skw") => {
+                    format!("{}/{}", group(&numerator), group(&denominator))
+                }
+                Some("noBar") => format!("{{{} \\atop {}}}", numerator, denominator),
+                _ => format!("\\frac{{{}}}{{{}}}", numerator, denominator),
+            }
+        }
+        "rad" => {
+            let degree = argument("deg");
+            match node.is_on("degHide") || degree.is_empty() {
+                true => format!("\\sqrt{{{}}}", argument("e")),
+                false => format!("\\sqrt[{}]{{{}}}", degree, argument("e")),
+            }
+        }
+        "sSup" => format!("{}^{{{}}}", group(&argument("e")), argument("sup")),
+        "sSub" => format!("{}_{{{}}}", group(&argument("e")), argument("sub")),
+        "sSubSup" => format!(
+            "{}_{{{}}}^{{{}}}",
+            group(&argument("e")),
+            argument("sub"),
+            argument("sup")
+        ),
+        "sPre" => format!(
+            "{{}}_{{{}}}^{{{}}}{}",
+            argument("sub"),
+            argument("sup"),
+            argument("e")
+        ),
+        "nary" => {
+            let operator = node.property("chr").unwrap_or("∫");
+            let mut latex = operator
+                .chars()
+                .next()
+                .and_then(|c| lookup(&LATEX_OPERATORS, c))
+                .map_or_else(|| latex_text(operator), str::to_string);
+            if !node.is_on("subHide") && node.child("sub").is_some_and(|sub| !is_empty_limit(sub)) {
+                latex.push_str(&format!("_{{{}}}", argument("sub")));
+            }
+            if !node.is_on("supHide") && node.child("sup").is_some_and(|sup| !is_empty_limit(sup)) {
+                latex.push_str(&format!("^{{{}}}", argument("sup")));
+            }
+            format!("{} {}", latex, argument("e"))
+        }
+        "d" => {
+            let begin = node.property("begChr").unwrap_or("(");
+            let end = node.property("endChr").unwrap_or(")");
+            let separator = node.property("sepChr").unwrap_or("|");
+            let elements: Vec<String> = node.children("e").map(latex).collect();
+            format!(
+                "\\left{}{}\\right{}",
+                latex_delimiter(begin),
+                elements.join(&latex_text(separator)),
+                latex_delimiter(end)
+            )
+        }
+        "m" => {
+            let rows: Vec<String> = node
+                .children("mr")
+                .map(|row| row.children("e").map(latex).collect::<Vec<_>>().join(" & "))
+                .collect();
+            format!("\\begin{{matrix}}{}\\end{{matrix}}", rows.join(" \\\\ "))
+        }
+        "eqArr" => {
+            let rows: Vec<String> = node.children("e").map(latex).collect();
+            format!("\\begin{{aligned}}{}\\end{{aligned}}", rows.join(" \\\\ "))
+        }
+        "func" => {
+            let name = node.child("fName").map(name_text).unwrap_or_default();
+            let function = match LATEX_FUNCTIONS.contains(&name.as_str()) {
+                true => format!("\\{}", name),
+                false if name.chars().all(char::is_alphabetic) && name.chars().count() > 1 => {
+                    format!("\\operatorname{{{}}}", name)
+                }
+                false => latex_argument(node, "fName"),
+            };
+            let argument = argument("e");
+            match argument.starts_with(char::is_alphabetic) {
+                true => format!("{} {}", function, argument),
+                false => format!("{}{}", function, argument),
+            }
+        }
+        "acc" => {
+            let accent = node.property("chr").unwrap_or("\u{0302}");
+            match accent
+                .chars()
+                .next()
+                .and_then(|c| lookup(&LATEX_ACCENTS, c))
+            {
+                Some(command) => format!("{}{{{}}}", command, argument("e")),
+                None => format!("\\overset{{{}}}{{{}}}", latex_text(accent), argument("e")),
+            }
+        }
+        "bar" => match node.property("pos") {
+            Some("top") => format!("\\overline{{{}}}", argument("e")),
+            _ => format!("\\underline{{{}}}", argument("e")),
+        },
+        "groupChr" => match node.property("chr").unwrap_or("⏟") {
+            "⏟" => format!("\\underbrace{{{}}}", argument("e")),
+            "⏞" => format!("\\overbrace{{{}}}", argument("e")),
+            _ => argument("e"),
+        },
+        "limLow" => {
+            let base = argument("e");
+            match LATEX_FUNCTIONS.contains(&base.as_str()) {
+                true => format!("\\{}_{{{}}}", base, argument("lim")),
+                false => format!("\\underset{{{}}}{{{}}}", argument("lim"), base),
+            }
+        }
+        "limUpp" => format!("\\overset{{{}}}{{{}}}", argument("lim"), argument("e")),
+        name if name.ends_with("Pr") => String::new(),
+        _ => node.children.iter().map(latex).collect(),
+    }
+}
+
+/// Whether a limit of an n-ary operator is empty.
+fn is_empty_limit(node: &MathNode) -> bool {
+    latex(node).is_empty()
+}
+
+/// The plain text of a function name, e.g. `sin`.
+fn name_text(node: &MathNode) -> String {
+    match node.name.as_str() {
+        "r" => node.text(),
+        name if name.ends_with("Pr") => String::new(),
+        _ => node.children.iter().map(name_text).collect(),
+    }
+}
+
+const SUPERSCRIPTS: [(char, char); 17] = [
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('+', '⁺'),
+    ('-', '⁻'),
+    ('−', '⁻'),
+    ('=', '⁼'),
+    ('(', '⁽'),
+    (')', '⁾'),
+    ('n', 'ⁿ'),
+];
+
+const SUBSCRIPTS: [(char, char); 27] = [
+    ('0', '₀'),
+    ('1', '₁'),
+    ('2', '₂'),
+    ('3', '₃'),
+    ('4', '₄'),
+    ('5', '₅'),
+    ('6', '₆'),
+    ('7', '₇'),
+    ('8', '₈'),
+    ('9', '₉'),
+    ('+', '₊'),
+    ('-', '₋'),
+    ('−', '₋'),
+    ('=', '₌'),
+    ('(', '₍'),
+    (')', '₎'),
+    ('a', 'ₐ'),
+    ('e', 'ₑ'),
+    ('o', 'ₒ'),
+    ('x', 'ₓ'),
+    ('i', 'ᵢ'),
+    ('j', 'ⱼ'),
+    ('k', 'ₖ'),
+    ('m', 'ₘ'),
+    ('n', 'ₙ'),
+    ('t', 'ₜ'),
+    ('r', 'ᵣ'),
+];
+
+/// Parentheses around text longer than a single number or word.
+fn paren(text: &str) -> String {
+    let bracketed = text.starts_with('(') && text.ends_with(')');
+    match bracketed || text.chars().all(char::is_alphanumeric) {
+        true => text.to_string(),
+        false => format!("({})", text),
+    }
+}
+
+/// A superscript or subscript: with Unicode superscript or subscript characters when there are
+/// some for all its characters, or as `^(...)` or `_(...)`.
+fn script(text: &str, table: &[(char, char)], operator: char) -> String {
+    let scripted: Option<String> = text.chars().map(|c| lookup(table, c)).collect();
+    match scripted {
+        Some(scripted) if !text.is_empty() => scripted,
+        _ if text.is_empty() => String::new(),
+        _ => format!("{}{}", operator, paren(text)),
+    }
+}
+
+fn unicode_argument(node: &MathNode, name: &str) -> String {
+    node.child(name).map(unicode).unwrap_or_default()
+}
+
+/// Writes an element as linear Unicode text, close to the UnicodeMath linear format of Word.
+fn unicode(node: &MathNode) -> String {
+    let argument = |name| unicode_argument(node, name);
+    let superscript = |name| script(&argument(name), &SUPERSCRIPTS, '^');
+    let subscript = |name| script(&argument(name), &SUBSCRIPTS, '_');
+    match node.name.as_str() {
+        "r" => node.text(),
+        "f" => {
+            let (numerator, denominator) = (argument("num"), argument("den"));
+            match node.property("type") {
+                Some("noBar") => format!("({}¦{})", numerator, denominator),
+                _ => format!("{}/{}", paren(&numerator), paren(&denominator)),
+            }
+        }
+        "rad" => {
+            let degree = match node.is_on("degHide") {
+                true => String::new(),
+                false => argument("deg"),
+            };
+            match degree.as_str() {
+                "" => format!("√{}", paren(&argument("e"))),
+                "3" => format!("∛{}", paren(&argument("e"))),
+                "4" => format!("∜{}", paren(&argument("e"))),
+                degree => format!("√({}&{})", degree, argument("e")),
+            }
+        }
+        "sSup" => format!("{}{}", argument("e"), superscript("sup")),
+        "sSub" => format!("{}{}", argument("e"), subscript("sub")),
+        "sSubSup" => format!(
+            "{}{}{}",
+            argument("e"),
+            subscript("sub"),
+            superscript("sup")
+        ),
+        "sPre" => format!(
+            "{}{}{}",
+            subscript("sub"),
+            superscript("sup"),
+            argument("e")
+        ),
+        "nary" => {
+            let mut text = node.property("chr").unwrap_or("∫").to_string();
+            if !node.is_on("subHide") {
+                text.push_str(&subscript("sub"));
+            }
+            if !node.is_on("supHide") {
+                text.push_str(&superscript("sup"));
+            }
+            format!("{} {}", text, argument("e"))
+        }
+        "d" => {
+            let elements: Vec<String> = node.children("e").map(unicode).collect();
+            format!(
+                "{}{}{}",
+                node.property("begChr").unwrap_or("("),
+                elements.join(node.property("sepChr").unwrap_or("|")),
+                node.property("endChr").unwrap_or(")")
+            )
+        }
+        "m" => {
+            let rows: Vec<String> = node
+                .children("mr")
+                .map(|row| row.children("e").map(unicode).collect::<Vec<_>>().join("&"))
+                .collect();
+            format!("■({})", rows.join("@"))
+        }
+        "eqArr" => {
+            let rows: Vec<String> = node.children("e").map(unicode).collect();
+            format!("█({})", rows.join("@"))
+        }
+        "func" => {
+            let (name, argument) = (argument("fName"), argument("e"));
+            match argument.starts_with(['(', '[', '{']) {
+                true => format!("{}{}", name, argument),
+                false => format!("{} {}", name, argument),
+            }
+        }
+        "acc" => {
+            let accent = node.property("chr").unwrap_or("\u{0302}");
+            format!("{}{}", argument("e"), accent)
+        }
+        "bar" => {
+            let line = match node.property("pos") {
+                Some("top") => '\u{0305}',
+                _ => '\u{0332}',
+            };
+            argument("e").chars().flat_map(|c| [c, line]).collect()
+        }
+        "groupChr" => format!(
+            "{}{}",
+            node.property("chr").unwrap_or("⏟"),
+            paren(&argument("e"))
+        ),
+        "limLow" => format!("{}_{}", argument("e"), paren(&argument("lim"))),
+        "limUpp" => format!("{}^{}", argument("e"), paren(&argument("lim"))),
+        name if name.ends_with("Pr") => String::new(),
+        _ => node.children.iter().map(unicode).collect(),
+    }
+}
+
+impl MathNode {
+    /// The equation as LaTeX.
+    pub fn latex(&self) -> String {
+        latex(self)
+    }
+
+    /// The equation as linear Unicode text.
+    pub fn unicode(&self) -> String {
+        unicode(self)
+    }
+}
@@ -0,0 +1,411 @@
+//! List numbering (`numbering.xml`): the markers of numbered and bulleted paragraphs.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use xml::reader::{EventReader, XmlEvent};
+
+use super::{is_on, is_w, symbols, w_attribute, WORDPROCESSINGML};
+
+/// Word supports nine list levels.
+const LEVELS: usize = 9;
+
+/// The largest start value Word accepts for a list level.
+const MAX_START: u32 = 32767;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberFormat {
+    Decimal,
+    DecimalZero,
+    UpperRoman,
+    LowerRoman,
+    UpperLetter,
+    LowerLetter,
+    Bullet,
+    None,
+}
+
+impl NumberFormat {
+    fn from_value(value: &str) -> NumberFormat {
+        match value {
+            "decimalZero" => NumberFormat::DecimalZero,
+            "upperRoman" => NumberFormat::UpperRoman,
+            "lowerRoman" => NumberFormat::LowerRoman,
+            "upperLetter" => NumberFormat::UpperLetter,
+            "lowerLetter" => NumberFormat::LowerLetter,
+            "bullet" => NumberFormat::Bullet,
+            "none" => NumberFormat::None,
+            _ => NumberFormat::Decimal,
+        }
+    }
+
+    fn format(self, number: u32) -> String {
+        match self {
+            NumberFormat::Decimal | NumberFormat::Bullet => number.to_string(),
+            NumberFormat::DecimalZero => format!("{:02}", number),
+            NumberFormat::UpperRoman => roman(number),
+            NumberFormat::LowerRoman => roman(number).to_lowercase(),
+            NumberFormat::UpperLetter => letter(number),
+            NumberFormat::LowerLetter => letter(number).to_lowercase(),
+            NumberFormat::None => String::new(),
+        }
+    }
+}
+
+fn roman(mut number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut text = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            text.push_str(numeral);
+            number -= value;
+        }
+    }
+    text
+}
+
+/// Letters as Word writes them: A...Z, then AA...ZZ, AAA...
+fn letter(number: u32) -> String {
+    if number == 0 {
+        return String::new();
+    }
+    let letter = (b'A' + ((number - 1) % 26) as u8) as char;
+    letter.to_string().repeat(((number - 1) / 26 + 1) as usize)
+}
+
+#[derive(Debug, Clone)]
+struct Level {
+    start: u32,
+    format: NumberFormat,
+    /// The marker template, where `%1`...`%9` stand for the numbers of the levels.
+    text: String,
+    /// `w:lvlRestart`: the level is restarted by levels up to this 1-based index (0: never).
+    restart: Option<usize>,
+    /// `w:isLgl`: the numbers of the other levels are written as decimal numbers.
+    legal: bool,
+    /// No space between the marker and the text (`w:suff w:val="nothing"`).
+    no_suffix: bool,
+    /// The font of the marker, for the symbols of bullets.
+    font: Option<String>,
+}
+
+impl Default for Level {
+    fn default() -> Level {
+        Level {
+            start: 1,
+            format: NumberFormat::Decimal,
+            text: String::new(),
+            restart: None,
+            legal: false,
+            no_suffix: false,
+            font: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Num {
+    abstract_id: String,
+    /// `w:startOverride` by level.
+    starts: HashMap<usize, u32>,
+}
+
+/// The list definitions of a document.
+#[derive(Debug, Default)]
+pub(crate) struct Numbering {
+    abstracts: HashMap<String, HashMap<usize, Level>>,
+    nums: HashMap<String, Num>,
+}
+
+fn parse_level(value: Option<&str>) -> usize {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0usize)
+        .min(LEVELS - 1)
+}
+
+fn parse_start(value: Option<&str>) -> Option<u32> {
+    value
+        .and_then(|value| value.parse::<u32>().ok())
+        .map(|start| start.min(MAX_START))
+}
+
+impl Numbering {
+    pub fn parse(xml: &str) -> Result<Numbering> {
+        let mut numbering = Numbering::default();
+        // the abstract definition and level being read
+        let mut abstract_id: Option<String> = None;
+        let mut level: Option<(usize, Level)> = None;
+        // the list instance and level override being read
+        let mut num: Option<(String, Num)> = None;
+        let mut override_level = 0;
+        for event in EventReader::new(xml.as_bytes()) {
+            match event.context("Failed to parse XML")? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    if name.namespace.as_deref() != Some(WORDPROCESSINGML) {
+                        continue;
+                    }
+                    let local = name.local_name.as_str();
+                    let value = w_attribute(&attributes, "val");
+                    if local == "abstractNum" {
+                        abstract_id = w_attribute(&attributes, "abstractNumId").map(str::to_string);
+                    } else if local == "num" {
+                        num = w_attribute(&attributes, "numId")
+                            .map(|id| (id.to_string(), Num::default()));
+                    } else if let Some((_, num)) = &mut num {
+                        match local {
+                            "abstractNumId" => {
+                                num.abstract_id = value.unwrap_or_default().to_string()
+                            }
+                            "lvlOverride" => {
+                                override_level = parse_level(w_attribute(&attributes, "ilvl"))
+                            }
+                            "startOverride" => {
+                                if let Some(start) = parse_start(value) {
+                                    num.starts.insert(override_level, start);
+                                }
+                            }
+                            _ => {}
+                        }
+                    } else if abstract_id.is_none() {
+                        continue;
+                    } else if local == "lvl" {
+                        level = Some((
+                            parse_level(w_attribute(&attributes, "ilvl")),
+                            Level::default(),
+                        ));
+                    } else if let Some((_, level)) = &mut level {
+                        let value = value.unwrap_or_default();
+                        match local {
+                            "start" => level.start = parse_start(Some(value)).unwrap_or(1),
+                            "numFmt" => level.format = NumberFormat::from_value(value),
+                            "lvlText" => level.text = value.to_string(),
+                            "lvlRestart" => level.restart = value.parse().ok(),
+                            "isLgl" => level.legal = is_on(&attributes),
+                            "suff" => level.no_suffix = value == "nothing",
+                            "rFonts" => {
+                                level.font = w_attribute(&attributes, "ascii")
+                                    .or(w_attribute(&attributes, "hAnsi"))
+                                    .map(str::to_string)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                XmlEvent::EndElement { name } => {
+                    if is_w(&name, "lvl") {
+                        if let (Some(id), Some((index, level))) = (&abstract_id, level.take()) {
+                            numbering
+                                .abstracts
+                                .entry(id.clone())
+                                .or_default()
+                                .insert(index, level);
+                        }
+                    } else if is_w(&name, "abstractNum") {
+                        abstract_id = None;
+                    } else if is_w(&name, "num") {
+                        if let Some((id, num)) = num.take() {
+                            numbering.nums.insert(id, num);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(numbering)
+    }
+}
+
+/// The current number of each level of each list, as paragraphs are read in document order.
+#[derive(Debug, Default)]
+pub(crate) struct ListCounters {
+    /// By abstract definition: list instances sharing a definition continue each other's
+    /// numbering, unless they override the start of a level.
+    counters: HashMap<String, [Option<u32>; LEVELS]>,
+    /// The list instances already used.
+    started: HashSet<String>,
+}
+
+impl ListCounters {
+    /// Counts a paragraph of a list, and returns its marker followed by a space, e.g. `1.2.a `.
+    pub fn next_marker(
+        &mut self,
+        numbering: &Numbering,
+        num_id: &str,
+        level: usize,
+    ) -> Option<String> {
+        let num = numbering.nums.get(num_id)?;
+        let levels = numbering.abstracts.get(&num.abstract_id)?;
+        let definition = levels.get(&level)?;
+        let start = |index: usize| {
+            num.starts
+                .get(&index)
+                .or(levels.get(&index).map(|level| &level.start))
+                .copied()
+                .unwrap_or(1)
+        };
+
+        let counters = self.counters.entry(num.abstract_id.clone()).or_default();
+        if self.started.insert(num_id.to_string()) {
+            for index in num.starts.keys() {
+                counters[*index] = None;
+            }
+        }
+        counters[level] =
+            Some(counters[level].map_or(start(level), |count| count.saturating_add(1)));
+        for (index, counter) in counters.iter_mut().enumerate().skip(level + 1) {
+            let restart = match levels.get(&index).and_then(|level| level.restart) {
+                None => true,
+                Some(restart) => level < restart,
+            };
+            if restart {
+                *counter = None;
+            }
+        }
+
+        let mut marker = String::new();
+        let mut chars = definition.text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let index = chars
+                .peek()
+                .and_then(|next| next.to_digit(10))
+                .filter(|digit| c == '%' && (1..=LEVELS as u32).contains(digit));
+            match index {
+                Some(digit) => {
+                    chars.next();
+                    let index = digit as usize - 1;
+                    let format = match (definition.legal, levels.get(&index)) {
+                        (true, _) => NumberFormat::Decimal,
+                        (false, Some(level)) => level.format,
+                        (false, None) => NumberFormat::Decimal,
+                    };
+                    marker.push_str(&format.format(counters[index].unwrap_or(start(index))));
+                }
+                None if definition.format == NumberFormat::Bullet
+                    && ('\u{F000}'..='\u{F0FF}').contains(&c) =>
+                {
+                    let code = format!("{:X}", c as u32);
+                    marker.push(symbols::symbol(definition.font.as_deref(), &code).unwrap_or('•'));
+                }
+                None => marker.push(c),
+            }
+        }
+        if marker.is_empty() {
+            return None;
+        }
+        if !definition.no_suffix {
+            marker.push(' ');
+        }
+        Some(marker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::docx::testing::NAMESPACES;
+    use pretty_assertions::assert_eq;
+
+    fn numbering() -> Numbering {
+        Numbering::parse(&format!(
+            r#"<w:numbering {}>
+                <w:abstractNum w:abstractNumId="0">
+                    <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>
+                    <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1.%2."/></w:lvl>
+                    <w:lvl w:ilvl="2"><w:start w:val="1"/><w:numFmt w:val="lowerLetter"/><w:lvlText w:val="%1.%2.%3"/></w:lvl>
+                    <w:lvl w:ilvl="3"><w:start w:val="1"/><w:numFmt w:val="lowerRoman"/><w:lvlRestart w:val="0"/><w:lvlText w:val="(%4)"/></w:lvl>
+                </w:abstractNum>
+                <w:abstractNum w:abstractNumId="1">
+                    <w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/><w:lvlText w:val="&#xF0B7;"/><w:rPr><w:rFonts w:ascii="Symbol" w:hAnsi="Symbol"/></w:rPr></w:lvl>
+                    <w:lvl w:ilvl="1"><w:numFmt w:val="bullet"/><w:lvlText w:val="o"/><w:rPr><w:rFonts w:ascii="Courier New"/></w:rPr></w:lvl>
+                </w:abstractNum>
+                <w:abstractNum w:abstractNumId="2">
+                    <w:lvl w:ilvl="0"><w:start w:val="3"/><w:numFmt w:val="upperRoman"/><w:lvlText w:val="Article %1"/><w:suff w:val="nothing"/></w:lvl>
+                    <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="upperLetter"/><w:isLgl/><w:lvlText w:val="%1.%2"/></w:lvl>
+                </w:abstractNum>
+                <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+                <w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+                <w:num w:numId="3"><w:abstractNumId w:val="0"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="1"/></w:lvlOverride></w:num>
+                <w:num w:numId="4"><w:abstractNumId w:val="2"/></w:num>
+            </w:numbering>"#,
+            NAMESPACES
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_multilevel_markers() {
+        let numbering = numbering();
+        let mut counters = ListCounters::default();
+        let markers: Vec<String> = [0, 1, 2, 2, 3, 1, 2, 3, 0, 1]
+            .iter()
+            .map(|level| counters.next_marker(&numbering, "1", *level).unwrap())
+            .collect();
+
+        assert_eq!(
+            markers,
+            vec![
+                "1. ", "1.1. ", "1.1.a ", "1.1.b ", "(i) ", "1.2. ", "1.2.a ", "(ii) ", "2. ",
+                "2.1. "
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restart_and_formats() {
+        let numbering = numbering();
+        let mut counters = ListCounters::default();
+        let mut marker = |num_id, level| counters.next_marker(&numbering, num_id, level);
+
+        assert_eq!(marker("1", 0).as_deref(), Some("1. "));
+        assert_eq!(marker("1", 0).as_deref(), Some("2. "));
+        // a list instance sharing the definition continues the numbering, unless it restarts it
+        assert_eq!(marker("3", 0).as_deref(), Some("1. "));
+        assert_eq!(marker("3", 0).as_deref(), Some("2. "));
+        assert_eq!(marker("2", 0).as_deref(), Some("• "));
+        assert_eq!(marker("2", 1).as_deref(), Some("o "));
+        assert_eq!(marker("4", 0).as_deref(), Some("Article III"));
+        assert_eq!(marker("4", 1).as_deref(), Some("3.1 "));
+        assert_eq!(marker("9", 0), None);
+        assert_eq!(letter(28), "BB");
+    }
+
+    #[test]
+    fn test_start_limit() {
+        let numbering = Numbering::parse(&format!(
+            r#"<w:numbering {}>
+                <w:abstractNum w:abstractNumId="0">
+                    <w:lvl w:ilvl="0"><w:start w:val="4294967295"/><w:numFmt w:val="upperLetter"/><w:lvlText w:val="%1"/></w:lvl>
+                </w:abstractNum>
+                <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+                <w:num w:numId="2"><w:abstractNumId w:val="0"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="4294967295"/></w:lvlOverride></w:num>
+            </w:numbering>"#,
+            NAMESPACES
+        ))
+        .unwrap();
+        let mut counters = ListCounters::default();
+        let expected = format!("{} ", letter(MAX_START));
+
+        assert_eq!(
+            counters.next_marker(&numbering, "1", 0),
+            Some(expected.clone())
+        );
+        assert_eq!(counters.next_marker(&numbering, "2", 0), Some(expected));
+    }
+}
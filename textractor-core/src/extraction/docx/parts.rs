@@ -0,0 +1,276 @@
+use std::io::Cursor;
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+use zip::ZipArchive;
+
+use super::{is_w, read_part, w_attribute, BodyText, Definitions, DocxOptions, Relationship};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocxPartKind {
+    Header,
+    Footer,
+    Footnote,
+    Endnote,
+    Comment,
+}
+
+/// A header, footer, footnote, endnote or comment of a DOCX document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocxPart {
+    pub kind: DocxPartKind,
+    /// The `w:id` of a footnote, endnote or comment, as referenced from the body.
+    pub id: Option<String>,
+    /// The author of a comment.
+    pub author: Option<String>,
+    /// The date of a comment, as written in the document (e.g. `2024-03-04T14:00:00Z`).
+    pub date: Option<String>,
+    pub text: String,
+}
+
+impl DocxPart {
+    /// The part text, preceded by a label line such as `[Footnote 1]` or
+    /// `[Comment 3] Ada (2024-03-04T14:00:00Z)`.
+    pub fn labelled_text(&self) -> String {
+        let mut label = format!("[{:?}", self.kind);
+        if let Some(id) = &self.id {
+            label.push(' ');
+            label.push_str(id);
+        }
+        label.push(']');
+        if let Some(author) = &self.author {
+            label.push(' ');
+            label.push_str(author);
+        }
+        if let Some(date) = &self.date {
+            label.push_str(&format!(" ({})", date));
+        }
+        format!("{}\n{}", label, self.text)
+    }
+}
+
+/// Reads the text of each `w:{container}` element of a part, with the element attributes.
+fn read_entries(
+    xml: &str,
+    container: &str,
+    options: &DocxOptions,
+    definitions: &Rc<Definitions>,
+) -> Result<Vec<(Vec<OwnedAttribute>, String)>> {
+    let mut entries = Vec::new();
+    let mut current: Option<(Vec<OwnedAttribute>, BodyText)> = None;
+    for event in EventReader::new(xml.as_bytes()) {
+        let event = event.context("Failed to parse XML")?;
+        match &event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if current.is_none() && is_w(name, container) => {
+                current = Some((attributes.clone(), BodyText::new(options, definitions)));
+            }
+            XmlEvent::EndElement { name } if is_w(name, container) => {
+                if let Some((attributes, body)) = current.take() {
+                    let text = body.finish().pages.concat();
+                    entries.push((attributes, text.trim_end().to_string()));
+                }
+            }
+            event => {
+                if let Some((_, body)) = &mut current {
+                    body.handle(event);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads the headers, footers, notes and comments selected by the options, grouped by kind.
+pub(crate) fn read_parts(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    relationships: &[Relationship],
+    options: &DocxOptions,
+    definitions: &Rc<Definitions>,
+) -> Result<Vec<DocxPart>> {
+    let kinds = [
+        (
+            "header",
+            "hdr",
+            DocxPartKind::Header,
+            options.include_headers,
+        ),
+        (
+            "footer",
+            "ftr",
+            DocxPartKind::Footer,
+            options.include_footers,
+        ),
+        (
+            "footnotes",
+            "footnote",
+            DocxPartKind::Footnote,
+            options.include_footnotes,
+        ),
+        (
+            "endnotes",
+            "endnote",
+            DocxPartKind::Endnote,
+            options.include_endnotes,
+        ),
+        (
+            "comments",
+            "comment",
+            DocxPartKind::Comment,
+            options.include_comments,
+        ),
+    ];
+    if kinds.iter().all(|(_, _, _, included)| !included) {
+        return Ok(Vec::new());
+    }
+
+    let mut parts = Vec::new();
+    for (relationship, container, kind, included) in kinds {
+        if !included {
+            continue;
+        }
+        for target in relationships
+            .iter()
+            .filter(|r| r.kind == relationship)
+            .map(|r| r.target.clone())
+        {
+            let Some(xml) = read_part(archive, &target)? else {
+                continue;
+            };
+            let entries = read_entries(&xml, container, options, definitions)
+                .with_context(|| format!("Failed to read {}", target))?;
+            for (attributes, text) in entries {
+                // separators between the body and the notes are stored as notes too
+                if !matches!(w_attribute(&attributes, "type"), None | Some("normal")) {
+                    continue;
+                }
+                if text.is_empty() {
+                    continue;
+                }
+                let attribute = |name| w_attribute(&attributes, name).map(str::to_string);
+                let whole_part = matches!(kind, DocxPartKind::Header | DocxPartKind::Footer);
+                parts.push(DocxPart {
+                    kind,
+                    id: if whole_part { None } else { attribute("id") },
+                    author: attribute("author"),
+                    date: attribute("date"),
+                    text,
+                });
+            }
+        }
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::docx::testing::{docx, paragraph, NAMESPACES};
+    use crate::extraction::{DocxExtractor, Extract};
+    use pretty_assertions::assert_eq;
+
+    const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+        <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/>
+        <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/footer" Target="/word/footer1.xml"/>
+        <Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/footnotes" Target="footnotes.xml"/>
+        <Relationship Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/endnotes" Target="endnotes.xml"/>
+        <Relationship Id="rId5" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments" Target="comments.xml"/>
+    </Relationships>"#;
+
+    fn contract() -> Vec<u8> {
+        let header = format!(
+            "<w:hdr {}>{}</w:hdr>",
+            NAMESPACES,
+            paragraph("ACME Confidential")
+        );
+        let footer = format!("<w:ftr {}>{}</w:ftr>", NAMESPACES, paragraph("Draft 3"));
+        let footnotes = format!(
+            r#"<w:footnotes {}>
+                <w:footnote w:type="separator" w:id="-1"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>
+                <w:footnote w:type="continuationSeparator" w:id="0"><w:p><w:r><w:continuationSeparator/></w:r></w:p></w:footnote>
+                <w:footnote w:id="1">{}</w:footnote>
+            </w:footnotes>"#,
+            NAMESPACES,
+            paragraph("As amended in 2021.")
+        );
+        let endnotes = format!(
+            r#"<w:endnotes {}><w:endnote w:id="1">{}{}</w:endnote></w:endnotes>"#,
+            NAMESPACES,
+            paragraph("See the schedule."),
+            paragraph("Second paragraph.")
+        );
+        let comments = format!(
+            r#"<w:comments {}><w:comment w:id="0" w:author="Ada Lovelace" w:date="2024-03-04T14:00:00Z" w:initials="AL">{}</w:comment></w:comments>"#,
+            NAMESPACES,
+            paragraph("Is this term enforceable?")
+        );
+        docx(
+            &paragraph("The parties agree."),
+            &[
+                ("word/_rels/document.xml.rels", RELS),
+                ("word/header1.xml", &header),
+                ("word/footer1.xml", &footer),
+                ("word/footnotes.xml", &footnotes),
+                ("word/endnotes.xml", &endnotes),
+                ("word/comments.xml", &comments),
+            ],
+        )
+    }
+
+    fn all_parts() -> DocxOptions {
+        DocxOptions {
+            include_headers: true,
+            include_footers: true,
+            include_footnotes: true,
+            include_endnotes: true,
+            include_comments: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_read_parts() {
+        let document = DocxExtractor::extract_document(&contract(), &all_parts()).unwrap();
+
+        assert_eq!(
+            document.text(),
+            "The parties agree.\n\
+             \n[Header]\nACME Confidential\n\
+             \n[Footer]\nDraft 3\n\
+             \n[Footnote 1]\nAs amended in 2021.\n\
+             \n[Endnote 1]\nSee the schedule.\n\nSecond paragraph.\n\
+             \n[Comment 0] Ada Lovelace (2024-03-04T14:00:00Z)\nIs this term enforceable?\n"
+        );
+        assert_eq!(
+            document.parts[4],
+            DocxPart {
+                kind: DocxPartKind::Comment,
+                id: Some("0".to_string()),
+                author: Some("Ada Lovelace".to_string()),
+                date: Some("2024-03-04T14:00:00Z".to_string()),
+                text: "Is this term enforceable?".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_parts() {
+        let options = DocxOptions {
+            include_footnotes: true,
+            include_comments: true,
+            ..Default::default()
+        };
+        let document = DocxExtractor::extract_document(&contract(), &options).unwrap();
+        let kinds: Vec<DocxPartKind> = document.parts.iter().map(|part| part.kind).collect();
+
+        assert_eq!(kinds, vec![DocxPartKind::Footnote, DocxPartKind::Comment]);
+        // parts are opt-in
+        assert_eq!(
+            DocxExtractor::extract(&contract()).unwrap(),
+            "The parties agree.\n"
+        );
+    }
+}
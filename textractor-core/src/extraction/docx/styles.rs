@@ -0,0 +1,116 @@
+//! Paragraph styles (`styles.xml`): the outline level and list numbering they give paragraphs.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use xml::reader::{EventReader, XmlEvent};
+
+use super::{is_w, w_attribute};
+
+/// Styles can be based on each other in long chains, and occasionally in loops.
+const MAX_BASED_ON_DEPTH: usize = 16;
+
+#[derive(Debug, Default)]
+struct Style {
+    name: Option<String>,
+    based_on: Option<String>,
+    /// 0-based outline level; 9 is body text.
+    outline_level: Option<u32>,
+    num_id: Option<String>,
+    level: Option<usize>,
+}
+
+/// The paragraph styles of a document.
+#[derive(Debug, Default)]
+pub(crate) struct Styles {
+    styles: HashMap<String, Style>,
+    /// The style of paragraphs without `w:pStyle`.
+    default: Option<String>,
+}
+
+impl Styles {
+    pub fn parse(xml: &str) -> Result<Styles> {
+        let mut styles = Styles::default();
+        let mut current: Option<(String, Style)> = None;
+        for event in EventReader::new(xml.as_bytes()) {
+            match event.context("Failed to parse XML")? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    if is_w(&name, "style") {
+                        if w_attribute(&attributes, "type") != Some("paragraph") {
+                            continue;
+                        }
+                        let Some(id) = w_attribute(&attributes, "styleId") else {
+                            continue;
+                        };
+                        if matches!(
+                            w_attribute(&attributes, "default"),
+                            Some("1" | "true" | "on")
+                        ) {
+                            styles.default = Some(id.to_string());
+                        }
+                        current = Some((id.to_string(), Style::default()));
+                        continue;
+                    }
+                    let Some((_, style)) = &mut current else {
+                        continue;
+                    };
+                    let value = w_attribute(&attributes, "val").map(str::to_string);
+                    if is_w(&name, "name") {
+                        style.name = value;
+                    } else if is_w(&name, "basedOn") {
+                        style.based_on = value;
+                    } else if is_w(&name, "outlineLvl") {
+                        style.outline_level = value.and_then(|value| value.parse().ok());
+                    } else if is_w(&name, "numId") {
+                        style.num_id = value;
+                    } else if is_w(&name, "ilvl") {
+                        style.level = value.and_then(|value| value.parse().ok());
+                    }
+                }
+                XmlEvent::EndElement { name } if is_w(&name, "style") => {
+                    if let Some((id, style)) = current.take() {
+                        styles.styles.insert(id, style);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(styles)
+    }
+
+    /// The first value found along the chain of styles the given style is based on.
+    fn inherited<T>(&self, style: Option<&str>, value: impl Fn(&Style) -> Option<T>) -> Option<T> {
+        let mut id = style.or(self.default.as_deref())?;
+        for _ in 0..MAX_BASED_ON_DEPTH {
+            let style = self.styles.get(id)?;
+            if let Some(value) = value(style) {
+                return Some(value);
+            }
+            id = style.based_on.as_deref()?;
+        }
+        None
+    }
+
+    /// The 0-based outline level of paragraphs of the style: from `w:outlineLvl`, or from the
+    /// name of the built-in heading styles ("heading 1"...).
+    pub fn outline_level(&self, style: Option<&str>) -> Option<u32> {
+        self.inherited(style, |style| {
+            style.outline_level.or_else(|| {
+                let name = style.name.as_deref()?.to_lowercase();
+                let level: u32 = name.strip_prefix("heading ")?.parse().ok()?;
+                level.checked_sub(1)
+            })
+        })
+        .filter(|level| *level < 9)
+    }
+
+    /// The list instance and level of paragraphs of the style.
+    pub fn numbering(&self, style: Option<&str>) -> (Option<String>, Option<usize>) {
+        (
+            self.inherited(style, |style| style.num_id.clone()),
+            self.inherited(style, |style| style.level),
+        )
+    }
+}
@@ -0,0 +1,69 @@
+//! Characters of the symbol fonts used by `w:sym`.
+
+/// The Symbol font from 0x20 to 0x7E...
+const SYMBOL_LOW: &str = " !∀#∃%&∋()∗+,−./0123456789:;<=>?≅ΑΒΧΔΕΦΓΗΙϑΚΛΜΝΟΠΘΡΣΤΥςΩΞΨΖ[∴]⊥_‾αβχδεφγηιϕκλμνοπθρστυϖωξψζ{|}∼";
+/// ...and from 0xA0 to 0xFE (0xF0 is not assigned).
+const SYMBOL_HIGH: &str = "€ϒ′≤⁄∞ƒ♣♦♥♠↔←↑→↓°±″≥×∝∂•÷≠≡≈…⏐⎯↵ℵℑℜ℘⊗⊕∅∩∪⊃⊇⊄⊂⊆∈∉∠∇®©™∏√⋅¬∧∨⇔⇐⇑⇒⇓◊〈®©™∑⎛⎜⎝⎡⎢⎣⎧⎨⎩⎪\u{0}〉∫⌠⎮⌡⎞⎟⎠⎤⎥⎦⎫⎬⎭";
+
+/// Bullets and check marks of the Wingdings font.
+const WINGDINGS: [(u32, char); 14] = [
+    (0x6C, '●'),
+    (0x6E, '■'),
+    (0x6F, '□'),
+    (0x71, '❑'),
+    (0x75, '◆'),
+    (0x76, '❖'),
+    (0xA7, '▪'),
+    (0xA8, '◻'),
+    (0xD8, '➢'),
+    (0xE8, '➔'),
+    (0xFB, '✗'),
+    (0xFC, '✓'),
+    (0xFD, '☒'),
+    (0xFE, '☑'),
+];
+
+///
+/// The character of a `w:sym` element, from its font and hexadecimal character code. Symbol fonts
+/// map their codes to the private use area at U+F000, which is mapped back to Unicode for the
+/// Symbol and Wingdings fonts.
+pub(crate) fn symbol(font: Option<&str>, code: &str) -> Option<char> {
+    let code = u32::from_str_radix(code, 16).ok()?;
+    let byte = match code {
+        0xF000..=0xF0FF => code - 0xF000,
+        _ => return char::from_u32(code),
+    };
+    let font = font.unwrap_or_default().to_ascii_lowercase();
+    if font == "symbol" {
+        let symbol = match byte {
+            0x20..=0x7E => SYMBOL_LOW.chars().nth((byte - 0x20) as usize),
+            0xA0..=0xFE => SYMBOL_HIGH.chars().nth((byte - 0xA0) as usize),
+            _ => None,
+        };
+        symbol.filter(|c| *c != '\0')
+    } else if font.starts_with("wingdings") {
+        WINGDINGS
+            .iter()
+            .find(|(code, _)| *code == byte)
+            .map(|(_, c)| *c)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol() {
+        assert_eq!(SYMBOL_LOW.chars().count(), 0x7F - 0x20);
+        assert_eq!(SYMBOL_HIGH.chars().count(), 0xFF - 0xA0);
+        assert_eq!(symbol(Some("Symbol"), "F061"), Some('α'));
+        assert_eq!(symbol(Some("Symbol"), "F0B7"), Some('•'));
+        assert_eq!(symbol(Some("Symbol"), "F0E5"), Some('∑'));
+        assert_eq!(symbol(Some("Wingdings"), "F0FC"), Some('✓'));
+        assert_eq!(symbol(Some("Arial"), "2014"), Some('—'));
+        assert_eq!(symbol(Some("Webdings"), "F061"), None);
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+/// Word tables have at most 63 grid columns, larger spans are clamped to it.
+const MAX_COLUMNS: usize = 63;
+
+/// A table of a DOCX document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocxTable {
+    /// The cells of each row. A cell merged over several rows only appears in its first row.
+    pub rows: Vec<Vec<DocxCell>>,
+}
+
+/// A cell of a DOCX table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocxCell {
+    /// The text of the paragraphs of the cell, joined by spaces.
+    pub text: String,
+    /// 0-based index of the first grid column covered by the cell.
+    pub column: usize,
+    /// Number of grid columns covered by the cell (`w:gridSpan`).
+    pub column_span: usize,
+    /// Number of rows covered by the cell (`w:vMerge`).
+    pub row_span: usize,
+    /// Tables nested in the cell.
+    pub tables: Vec<DocxTable>,
+}
+
+impl DocxTable {
+    /// Number of grid columns of the table.
+    pub fn columns(&self) -> usize {
+        self.rows
+            .iter()
+            .flatten()
+            .map(|cell| cell.column.saturating_add(cell.column_span))
+            .max()
+            .unwrap_or(0)
+    }
+
+    ///
+    /// The table as text, one row per line with cells separated by tabs. Merged cells are
+    /// written in their first row and column, leaving the others empty so that columns line up.
+    /// Rows without any text are skipped.
+    pub fn text(&self) -> String {
+        let columns = self.columns();
+        let mut text = String::new();
+        for row in &self.rows {
+            if row.iter().all(|cell| cell.text.is_empty()) {
+                continue;
+            }
+            let mut slots = vec![""; columns];
+            for cell in row {
+                slots[cell.column] = &cell.text;
+            }
+            text.push_str(&slots.join("\t"));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The text of the table on a single line, as it appears in the cell of an outer table.
+    fn inline_text(&self) -> String {
+        self.rows
+            .iter()
+            .flatten()
+            .map(|cell| cell.text.as_str())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Merge {
+    None,
+    Restart,
+    Continue,
+}
+
+#[derive(Debug)]
+struct RawCell {
+    paragraphs: Vec<String>,
+    column_span: usize,
+    merge: Merge,
+    tables: Vec<DocxTable>,
+}
+
+/// A table being read, cell by cell.
+#[derive(Debug, Default)]
+pub(crate) struct TableBuilder {
+    /// The cells of each row, with the number of grid columns skipped before the row.
+    rows: Vec<(usize, Vec<RawCell>)>,
+}
+
+impl TableBuilder {
+    pub fn start_row(&mut self) {
+        self.rows.push((0, Vec::new()));
+    }
+
+    pub fn start_cell(&mut self) {
+        if let Some((_, cells)) = self.rows.last_mut() {
+            cells.push(RawCell {
+                paragraphs: Vec::new(),
+                column_span: 1,
+                merge: Merge::None,
+                tables: Vec::new(),
+            });
+        }
+    }
+
+    fn cell(&mut self) -> Option<&mut RawCell> {
+        self.rows.last_mut()?.1.last_mut()
+    }
+
+    /// `w:gridBefore`, the number of grid columns left empty before the row.
+    pub fn set_grid_before(&mut self, columns: usize) {
+        if let Some((before, _)) = self.rows.last_mut() {
+            *before = columns.min(MAX_COLUMNS);
+        }
+    }
+
+    /// `w:gridSpan` of the current cell.
+    pub fn set_column_span(&mut self, columns: usize) {
+        if let Some(cell) = self.cell() {
+            cell.column_span = columns.clamp(1, MAX_COLUMNS);
+        }
+    }
+
+    /// `w:vMerge` of the current cell, with its `w:val` attribute.
+    pub fn set_vertical_merge(&mut self, value: Option<&str>) {
+        if let Some(cell) = self.cell() {
+            cell.merge = match value {
+                Some("restart") => Merge::Restart,
+                _ => Merge::Continue,
+            };
+        }
+    }
+
+    /// Adds a paragraph to the current cell, on a single line.
+    pub fn push_paragraph(&mut self, text: &str) -> bool {
+        match self.cell() {
+            Some(cell) => {
+                // tabs and breaks inside a cell would break the rows of the table text
+                cell.paragraphs
+                    .push(text.split_whitespace().collect::<Vec<_>>().join(" "));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a nested table to the current cell, its text flowing with the paragraphs.
+    pub fn push_table(&mut self, table: DocxTable) {
+        if let Some(cell) = self.cell() {
+            cell.paragraphs.push(table.inline_text());
+            cell.tables.push(table);
+        }
+    }
+
+    /// Lays the cells out on the table grid, folding vertically merged cells into the cell
+    /// that starts the merge.
+    pub fn build(self) -> DocxTable {
+        let mut rows: Vec<Vec<DocxCell>> = Vec::new();
+        // the cell (row, index) currently covering each grid column
+        let mut origins: HashMap<usize, (usize, usize)> = HashMap::new();
+        for (before, cells) in self.rows {
+            let mut row = Vec::new();
+            let mut column = before;
+            for cell in cells {
+                let text = cell
+                    .paragraphs
+                    .iter()
+                    .filter(|paragraph| !paragraph.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let origin = match cell.merge {
+                    Merge::Continue => origins.get(&column).copied(),
+                    _ => None,
+                };
+                match origin {
+                    Some((r, i)) => {
+                        let merged: &mut DocxCell = &mut rows[r][i];
+                        merged.row_span += 1;
+                        if !text.is_empty() {
+                            if !merged.text.is_empty() {
+                                merged.text.push(' ');
+                            }
+                            merged.text.push_str(&text);
+                        }
+                        merged.tables.extend(cell.tables);
+                    }
+                    None => {
+                        for covered in column..column.saturating_add(cell.column_span) {
+                            origins.insert(covered, (rows.len(), row.len()));
+                        }
+                        row.push(DocxCell {
+                            text,
+                            column,
+                            column_span: cell.column_span,
+                            row_span: 1,
+                            tables: cell.tables,
+                        });
+                    }
+                }
+                column = column.saturating_add(cell.column_span);
+            }
+            rows.push(row);
+        }
+        DocxTable { rows }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::extraction::docx::testing::{docx, paragraph};
+    use crate::extraction::{DocxExtractor, DocxOptions, Extract};
+    use pretty_assertions::assert_eq;
+
+    fn cell(properties: &str, content: &str) -> String {
+        format!("<w:tc><w:tcPr>{}</w:tcPr>{}</w:tc>", properties, content)
+    }
+
+    fn row(cells: &[String]) -> String {
+        format!("<w:tr>{}</w:tr>", cells.concat())
+    }
+
+    fn table(rows: &[String]) -> String {
+        format!(
+            "<w:tbl><w:tblGrid><w:gridCol/><w:gridCol/><w:gridCol/></w:tblGrid>{}</w:tbl>",
+            rows.concat()
+        )
+    }
+
+    fn schedule() -> Vec<u8> {
+        let nested = table(&[
+            row(&[cell("", &paragraph("Mon")), cell("", &paragraph("9:00"))]),
+            row(&[cell("", &paragraph("Tue")), cell("", &paragraph("10:00"))]),
+        ]);
+        let body = [
+            paragraph("Course schedule"),
+            table(&[
+                row(&[
+                    cell("", &paragraph("Course")),
+                    cell(r#"<w:gridSpan w:val="2"/>"#, &paragraph("Details")),
+                ]),
+                row(&[
+                    cell(r#"<w:vMerge w:val="restart"/>"#, &paragraph("Chemistry")),
+                    cell(
+                        "",
+                        &format!("{}{}", paragraph("Lab A"), paragraph("2nd floor")),
+                    ),
+                    cell("", &nested),
+                ]),
+                row(&[
+                    cell("<w:vMerge/>", "<w:p/>"),
+                    cell("", &paragraph("Lab B")),
+                    cell("", &paragraph("Fri 14:00")),
+                ]),
+            ]),
+            paragraph("See you there"),
+        ];
+        docx(&body.concat(), &[])
+    }
+
+    #[test]
+    fn test_table_text() {
+        let text = DocxExtractor::extract(&schedule()).unwrap();
+
+        assert_eq!(
+            text,
+            "Course schedule\n\n\
+             Course\tDetails\t\n\
+             Chemistry\tLab A 2nd floor\tMon 9:00 Tue 10:00\n\
+             \tLab B\tFri 14:00\n\n\
+             See you there\n"
+        );
+    }
+
+    #[test]
+    fn test_table_cells() {
+        let document =
+            DocxExtractor::extract_document(&schedule(), &DocxOptions::default()).unwrap();
+        let table = &document.tables[0];
+
+        assert_eq!(document.tables.len(), 1);
+        assert_eq!(table.columns(), 3);
+        assert_eq!(table.rows[0][1].column_span, 2);
+        assert_eq!(table.rows[1][0].text, "Chemistry");
+        assert_eq!(table.rows[1][0].row_span, 2);
+        // the merged cell only appears in its first row
+        assert_eq!(table.rows[2].len(), 2);
+        assert_eq!(table.rows[2][0].column, 1);
+        assert_eq!(table.rows[1][2].tables[0].text(), "Mon\t9:00\nTue\t10:00\n");
+    }
+
+    #[test]
+    fn test_oversized_spans() {
+        let body = table(&[format!(
+            r#"<w:tr><w:trPr><w:gridBefore w:val="4294967295"/></w:trPr>{}{}</w:tr>"#,
+            cell(r#"<w:gridSpan w:val="4294967295"/>"#, &paragraph("Wide")),
+            cell("", &paragraph("Last")),
+        )]);
+        let document =
+            DocxExtractor::extract_document(&docx(&body, &[]), &DocxOptions::default()).unwrap();
+        let table = &document.tables[0];
+
+        assert_eq!(table.rows[0][0].column, 63);
+        assert_eq!(table.rows[0][0].column_span, 63);
+        assert_eq!(table.columns(), 127);
+    }
+}
@@ -0,0 +1,53 @@
+//! Helpers to build small DOCX packages in memory for tests.
+
+use std::io::{Cursor, Write};
+
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS_PART: &str = "word/_rels/document.xml.rels";
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"></Relationships>"#;
+
+/// Namespace declarations for the root element of WordprocessingML parts.
+pub(crate) const NAMESPACES: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006" xmlns:m="http://schemas.openxmlformats.org/officeDocument/2006/math""#;
+
+/// A DOCX package with the given body content, and any additional parts (including the
+/// relationships of the document, `word/_rels/document.xml.rels`).
+pub(crate) fn docx(body: &str, parts: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document {}><w:body>{}</w:body></w:document>"#,
+        NAMESPACES, body
+    );
+    // content sniffing expects the parts of the `word` folder right after the package parts
+    let rels = parts
+        .iter()
+        .find(|(name, _)| *name == DOCUMENT_RELS_PART)
+        .map_or(DOCUMENT_RELS, |(_, rels)| *rels);
+    let mut files = vec![
+        ("[Content_Types].xml", CONTENT_TYPES),
+        ("_rels/.rels", PACKAGE_RELS),
+        ("word/document.xml", document.as_str()),
+        (DOCUMENT_RELS_PART, rels),
+    ];
+    files.extend(parts.iter().filter(|(name, _)| *name != DOCUMENT_RELS_PART));
+    for (name, content) in files {
+        writer.start_file(name, options).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// A paragraph with a single run of text.
+pub(crate) fn paragraph(text: &str) -> String {
+    format!(
+        "<w:p><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>",
+        text
+    )
+}
@@ -0,0 +1,304 @@
+use std::io::{Cursor, Read};
+
+use anyhow::{bail, Context, Result};
+use flate2::read::DeflateDecoder;
+use xml::reader::{EventReader, XmlEvent};
+use zip::ZipArchive;
+
+use super::Extract;
+
+pub struct HwpExtractor;
+pub struct HwpxExtractor;
+
+const HWP_SIGNATURE: &[u8] = b"HWP Document File";
+
+const HWPTAG_PARA_TEXT: u32 = 0x10 + 51;
+
+// FileHeader property flags
+const FLAG_COMPRESSED: u32 = 1;
+const FLAG_ENCRYPTED: u32 = 1 << 1;
+const FLAG_DISTRIBUTION: u32 = 1 << 2;
+
+///
+/// Decodes the UTF-16LE payload of a HWPTAG_PARA_TEXT record. Control characters below 32 are
+/// either a single code unit or, for inline and extended controls (tables, fields, tabs...),
+/// take up eight code units that carry no text.
+fn decode_para_text(payload: &[u8]) -> String {
+    let units: Vec<u16> = payload
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    let mut text = Vec::with_capacity(units.len());
+    let mut i = 0;
+    while i < units.len() {
+        let unit = units[i];
+        match unit {
+            9 => {
+                text.push(u16::from(b'\t'));
+                i += 8;
+            }
+            1..=8 | 11 | 12 | 14..=23 => i += 8,
+            10 => {
+                text.push(u16::from(b'\n'));
+                i += 1;
+            }
+            24 => {
+                text.push(u16::from(b'-'));
+                i += 1;
+            }
+            30 | 31 => {
+                text.push(u16::from(b' '));
+                i += 1;
+            }
+            0..=31 => i += 1,
+            _ => {
+                text.push(unit);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf16_lossy(&text)
+}
+
+/// Walks the records of a BodyText section and collects the text of every paragraph.
+fn section_text(section: &[u8], output: &mut String) -> Result<()> {
+    let mut pos = 0;
+    while pos + 4 <= section.len() {
+        let header = u32::from_le_bytes(section[pos..pos + 4].try_into().unwrap());
+        pos += 4;
+
+        let tag = header & 0x3FF;
+        let mut size = (header >> 20) as usize;
+        if size == 0xFFF {
+            let extended = section
+                .get(pos..pos + 4)
+                .context("Truncated HWP record header")?;
+            size = u32::from_le_bytes(extended.try_into().unwrap()) as usize;
+            pos += 4;
+        }
+
+        let payload = section
+            .get(pos..pos + size)
+            .context("Truncated HWP record")?;
+        pos += size;
+
+        if tag == HWPTAG_PARA_TEXT {
+            output.push_str(decode_para_text(payload).trim_end_matches('\n'));
+            output.push('\n');
+        }
+    }
+    Ok(())
+}
+
+impl Extract for HwpExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        let mut file = cfb::CompoundFile::open(Cursor::new(data))
+            .context("Failed to read HWP compound file")?;
+
+        let mut header = Vec::new();
+        file.open_stream("/FileHeader")
+            .context("HWP file is missing its FileHeader stream")?
+            .read_to_end(&mut header)?;
+        if !header.starts_with(HWP_SIGNATURE) || header.len() < 40 {
+            bail!("Not a HWP 5.0 document");
+        }
+
+        let flags = u32::from_le_bytes(header[36..40].try_into().unwrap());
+        if flags & FLAG_ENCRYPTED != 0 {
+            bail!("Password protected HWP documents are not supported");
+        }
+        if flags & FLAG_DISTRIBUTION != 0 {
+            bail!("HWP distribution documents are not supported");
+        }
+
+        let mut text = String::new();
+        let mut index = 0;
+        while file.is_stream(format!("/BodyText/Section{}", index)) {
+            let mut raw = Vec::new();
+            file.open_stream(format!("/BodyText/Section{}", index))?
+                .read_to_end(&mut raw)?;
+
+            let section = if flags & FLAG_COMPRESSED != 0 {
+                let mut inflated = Vec::new();
+                DeflateDecoder::new(raw.as_slice())
+                    .read_to_end(&mut inflated)
+                    .with_context(|| format!("Failed to decompress HWP section {}", index))?;
+                inflated
+            } else {
+                raw
+            };
+
+            section_text(&section, &mut text)?;
+            index += 1;
+        }
+
+        Ok(text)
+    }
+}
+
+impl Extract for HwpxExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        let cursor = Cursor::new(data);
+        let mut archive = ZipArchive::new(cursor).context("Failed to read ZIP archive")?;
+
+        // sections are named Contents/section0.xml, section1.xml, ... and must be read in order
+        let mut sections: Vec<(usize, String)> = archive
+            .file_names()
+            .filter_map(|name| {
+                let number = name
+                    .strip_prefix("Contents/section")?
+                    .strip_suffix(".xml")?
+                    .parse::<usize>()
+                    .ok()?;
+                Some((number, name.to_string()))
+            })
+            .collect();
+        sections.sort();
+
+        let mut result_text = String::new();
+        for (_, name) in sections {
+            let mut content = String::new();
+            archive
+                .by_name(&name)
+                .context("Failed to access file in ZIP archive")?
+                .read_to_string(&mut content)
+                .context("Failed to read section content")?;
+
+            let parser = EventReader::new(content.as_bytes());
+            let mut is_text = false;
+
+            for event in parser {
+                match event {
+                    Ok(XmlEvent::StartElement { name, .. }) => match name.local_name.as_str() {
+                        "t" => is_text = true,
+                        "tab" if is_text => result_text.push('\t'),
+                        "lineBreak" if is_text => result_text.push('\n'),
+                        _ => {}
+                    },
+                    Ok(XmlEvent::Characters(chars)) | Ok(XmlEvent::Whitespace(chars))
+                        if is_text =>
+                    {
+                        result_text.push_str(&chars);
+                    }
+                    Ok(XmlEvent::EndElement { name }) => match name.local_name.as_str() {
+                        "t" => is_text = false,
+                        "p" if !result_text.is_empty() && !result_text.ends_with('\n') => {
+                            result_text.push('\n')
+                        }
+                        _ => {}
+                    },
+                    Err(e) => {
+                        return Err(anyhow::Error::new(e).context("Failed to parse XML"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(result_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::ContentType;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    fn record(tag: u32, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = (tag | ((payload.len() as u32) << 20))
+            .to_le_bytes()
+            .to_vec();
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn para_text(units: &[u16]) -> Vec<u8> {
+        units.iter().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    fn hwp_document(sections: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+
+        let mut header = vec![0u8; 256];
+        header[..HWP_SIGNATURE.len()].copy_from_slice(HWP_SIGNATURE);
+        header[32..36].copy_from_slice(&0x05000300u32.to_le_bytes());
+        header[36..40].copy_from_slice(&FLAG_COMPRESSED.to_le_bytes());
+        file.create_stream("/FileHeader")
+            .unwrap()
+            .write_all(&header)
+            .unwrap();
+
+        file.create_storage("/BodyText").unwrap();
+        for (i, section) in sections.iter().enumerate() {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(section).unwrap();
+            let compressed = encoder.finish().unwrap();
+            file.create_stream(format!("/BodyText/Section{}", i))
+                .unwrap()
+                .write_all(&compressed)
+                .unwrap();
+        }
+        file.flush().unwrap();
+        file.into_inner().into_inner()
+    }
+
+    #[test]
+    fn test_extract_hwp() {
+        let mut first: Vec<u16> = "안녕하세요".encode_utf16().collect();
+        // an extended control (e.g. a table anchor) is eight code units wide
+        first.extend_from_slice(&[11, 0x6c74, 0x6274, 0, 0, 0, 0, 11]);
+        first.extend(" world".encode_utf16());
+        first.push(13);
+
+        let mut second: Vec<u16> = "Name".encode_utf16().collect();
+        second.extend_from_slice(&[9, 0, 0, 0, 0, 0, 0, 9]);
+        second.extend("Value".encode_utf16());
+        second.push(13);
+
+        let mut section = record(0x10 + 50, &[0; 22]);
+        section.extend(record(HWPTAG_PARA_TEXT, &para_text(&first)));
+        section.extend(record(0x10 + 50, &[0; 22]));
+        section.extend(record(HWPTAG_PARA_TEXT, &para_text(&second)));
+
+        let data = hwp_document(&[section]);
+        assert_eq!(ContentType::from(data.as_slice()), ContentType::Hwp);
+
+        let text = HwpExtractor::extract(&data).unwrap();
+
+        assert_eq!(text, "안녕하세요 world\nName\tValue\n");
+    }
+
+    #[test]
+    fn test_extract_hwpx() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        let stored = options.compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("mimetype", stored).unwrap();
+        writer.write_all(b"application/hwp+zip").unwrap();
+        for (name, body) in [
+            ("Contents/section1.xml", "<hp:p><hp:run><hp:t>Second section</hp:t></hp:run></hp:p>"),
+            (
+                "Contents/section0.xml",
+                "<hp:p><hp:run><hp:t>First <hp:tab/>line</hp:t></hp:run></hp:p><hp:p><hp:run><hp:t>한글</hp:t></hp:run></hp:p>",
+            ),
+        ] {
+            writer.start_file(name, options).unwrap();
+            let xml = format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><hs:sec xmlns:hs="http://www.hancom.co.kr/hwpml/2011/section" xmlns:hp="http://www.hancom.co.kr/hwpml/2011/paragraph">{}</hs:sec>"#,
+                body
+            );
+            writer.write_all(xml.as_bytes()).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+        assert_eq!(ContentType::from(data.as_slice()), ContentType::Hwpx);
+
+        let text = HwpxExtractor::extract(&data).unwrap();
+
+        assert_eq!(text, "First \tline\n한글\nSecond section\n");
+    }
+}
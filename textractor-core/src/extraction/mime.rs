@@ -0,0 +1,82 @@
+//! MIME helpers shared by the MHTML and vCard/iCalendar extractors.
+
+use base64::Engine;
+use encoding_rs::Encoding;
+
+///
+/// Decodes quoted-printable text. Soft line breaks (`=` at the end of a line, or of the
+/// input) are removed and malformed escapes are kept as-is.
+pub(crate) fn decode_quoted_printable(text: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] != b'=' {
+            result.push(text[i]);
+            i += 1;
+            continue;
+        }
+
+        let rest = &text[i + 1..];
+        if rest.is_empty() {
+            i += 1;
+        } else if rest.starts_with(b"\r\n") {
+            i += 3;
+        } else if rest.starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = rest
+            .get(..2)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+            result.push(byte);
+            i += 3;
+        } else {
+            result.push(b'=');
+            i += 1;
+        }
+    }
+    result
+}
+
+/// Decodes base64 content, ignoring the line breaks MIME inserts every 76 characters.
+pub(crate) fn decode_base64(text: &[u8]) -> Option<Vec<u8>> {
+    let compact: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .ok()
+}
+
+/// Decodes bytes in the given charset label, falling back to UTF-8 for unknown labels.
+pub(crate) fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_quoted_printable() {
+        let decoded = decode_quoted_printable(b"caf=C3=A9 =3D soft=\r\nbreak=\nagain=");
+
+        assert_eq!(String::from_utf8(decoded).unwrap(), "café = softbreakagain");
+    }
+
+    #[test]
+    fn test_decode_charset() {
+        assert_eq!(
+            decode_charset(&[0x93, 0x68, 0x69, 0x94], Some("windows-1252")),
+            "\u{201c}hi\u{201d}"
+        );
+        assert_eq!(decode_charset("ü".as_bytes(), None), "ü");
+    }
+}
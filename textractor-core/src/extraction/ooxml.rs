@@ -0,0 +1,314 @@
+//! Office Open XML packages (DOCX, PPTX, XLSX): parts, relationships and document properties.
+
+use std::io::{Cursor, Read};
+
+use anyhow::{Context, Result};
+use time::OffsetDateTime;
+use xml::reader::{EventReader, XmlEvent};
+use zip::ZipArchive;
+
+use super::dates::parse_iso_date;
+
+/// The document properties of an Office Open XML package: core properties (`docProps/core.xml`),
+/// extended properties of the application (`docProps/app.xml`) and custom properties
+/// (`docProps/custom.xml`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OoxmlMetadata {
+    pub title: Option<String>,
+    pub subject: Option<String>,
+    pub creator: Option<String>,
+    pub keywords: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub last_modified_by: Option<String>,
+    /// The number of times the document was saved.
+    pub revision: Option<u32>,
+    pub created: Option<OffsetDateTime>,
+    pub modified: Option<OffsetDateTime>,
+    pub last_printed: Option<OffsetDateTime>,
+    /// The application that last saved the document, e.g. "Microsoft Office Word".
+    pub application: Option<String>,
+    pub company: Option<String>,
+    pub manager: Option<String>,
+    pub template: Option<String>,
+    /// Statistics of the document, as recorded by the application when it was last saved.
+    pub pages: Option<u32>,
+    pub words: Option<u32>,
+    pub characters: Option<u32>,
+    pub paragraphs: Option<u32>,
+    pub slides: Option<u32>,
+    /// Total editing time, in minutes.
+    pub total_time: Option<u32>,
+    /// Custom properties as name and value, in document order. Values are written as in the
+    /// document: `true`, `42`, `2024-03-04T14:00:00Z`...
+    pub custom: Vec<(String, String)>,
+}
+
+pub(crate) fn read_part(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(anyhow::Error::new(e).context("Failed to access file in ZIP archive")),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .with_context(|| format!("Failed to read {}", name))?;
+    Ok(Some(content))
+}
+
+/// A relationship from a package part to another part, or to an external resource.
+#[derive(Debug)]
+pub(crate) struct Relationship {
+    /// The last segment of the relationship type, e.g. `header` or `hyperlink`.
+    pub kind: String,
+    /// The name of the target part, or the URI of an external target.
+    pub target: String,
+}
+
+/// Resolves a relationship target against the folder of the source part.
+fn resolve_target(folder: &str, target: &str) -> String {
+    let path = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("{}{}", folder, target),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// The relationships of a part (`""` for the package itself), in the order of the rels part.
+pub(crate) fn relationships(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    part: &str,
+) -> Result<Vec<Relationship>> {
+    let (folder, file) = match part.rfind('/') {
+        Some(i) => part.split_at(i + 1),
+        None => ("", part),
+    };
+    let Some(rels) = read_part(archive, &format!("{}_rels/{}.rels", folder, file))? else {
+        return Ok(Vec::new());
+    };
+    let mut relationships = Vec::new();
+    for event in EventReader::new(rels.as_bytes()) {
+        if let Ok(XmlEvent::StartElement { attributes, .. }) = event {
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|attribute| attribute.name.local_name == name)
+                    .map(|attribute| attribute.value.as_str())
+            };
+            let (Some(kind), Some(target)) = (attribute("Type"), attribute("Target")) else {
+                continue;
+            };
+            let external = attribute("TargetMode") == Some("External");
+            relationships.push(Relationship {
+                kind: kind.rsplit('/').next().unwrap_or(kind).to_string(),
+                target: match external {
+                    true => target.to_string(),
+                    false => resolve_target(folder, target),
+                },
+            });
+        }
+    }
+    Ok(relationships)
+}
+
+/// The text of each element directly below the root of a properties part, by local name. Custom
+/// properties are named by their `name` attribute and valued by the text of their child element.
+fn properties(xml: &str) -> Vec<(String, String)> {
+    let mut properties = Vec::new();
+    let mut depth = 0;
+    let mut current: Option<(String, String)> = None;
+    // stop at the first error, keeping the properties read so far
+    for event in EventReader::new(xml.as_bytes())
+        .into_iter()
+        .map_while(Result::ok)
+    {
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                depth += 1;
+                if depth == 2 {
+                    let property = attributes
+                        .iter()
+                        .find(|attribute| attribute.name.local_name == "name")
+                        .map_or(name.local_name, |attribute| attribute.value.clone());
+                    current = Some((property, String::new()));
+                }
+            }
+            XmlEvent::Characters(text) => {
+                if let Some((_, value)) = &mut current {
+                    value.push_str(&text);
+                }
+            }
+            XmlEvent::EndElement { .. } => {
+                depth -= 1;
+                if depth == 1 {
+                    if let Some((name, value)) = current.take() {
+                        properties.push((name, value.trim().to_string()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    properties
+}
+
+impl OoxmlMetadata {
+    /// Reads the document properties of an Office Open XML file.
+    pub fn read(data: &[u8]) -> Result<OoxmlMetadata> {
+        let mut archive =
+            ZipArchive::new(Cursor::new(data)).context("Failed to read ZIP archive")?;
+        OoxmlMetadata::read_archive(&mut archive)
+    }
+
+    pub(crate) fn read_archive(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<OoxmlMetadata> {
+        let mut parts = vec![
+            ("core-properties", "docProps/core.xml".to_string()),
+            ("extended-properties", "docProps/app.xml".to_string()),
+            ("custom-properties", "docProps/custom.xml".to_string()),
+        ];
+        for relationship in relationships(archive, "")? {
+            if let Some((_, part)) = parts
+                .iter_mut()
+                .find(|(kind, _)| *kind == relationship.kind)
+            {
+                *part = relationship.target;
+            }
+        }
+
+        let mut metadata = OoxmlMetadata::default();
+        for (kind, part) in parts {
+            let Some(xml) = read_part(archive, &part)? else {
+                continue;
+            };
+            for (name, value) in properties(&xml) {
+                if kind == "custom-properties" {
+                    metadata.custom.push((name, value));
+                    continue;
+                }
+                if value.is_empty() {
+                    continue;
+                }
+                let number = || value.parse().ok();
+                match name.as_str() {
+                    "title" => metadata.title = Some(value),
+                    "subject" => metadata.subject = Some(value),
+                    "creator" => metadata.creator = Some(value),
+                    "keywords" => metadata.keywords = Some(value),
+                    "description" => metadata.description = Some(value),
+                    "category" => metadata.category = Some(value),
+                    "lastModifiedBy" => metadata.last_modified_by = Some(value),
+                    "revision" => metadata.revision = number(),
+                    "created" => metadata.created = parse_iso_date(&value),
+                    "modified" => metadata.modified = parse_iso_date(&value),
+                    "lastPrinted" => metadata.last_printed = parse_iso_date(&value),
+                    "Application" => metadata.application = Some(value),
+                    "Company" => metadata.company = Some(value),
+                    "Manager" => metadata.manager = Some(value),
+                    "Template" => metadata.template = Some(value),
+                    "Pages" => metadata.pages = number(),
+                    "Words" => metadata.words = number(),
+                    "Characters" => metadata.characters = number(),
+                    "Paragraphs" => metadata.paragraphs = number(),
+                    "Slides" => metadata.slides = number(),
+                    "TotalTime" => metadata.total_time = number(),
+                    _ => {}
+                }
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::docx::testing::{docx, paragraph};
+    use pretty_assertions::assert_eq;
+    use time::macros::datetime;
+
+    const CORE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+        <cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+            <dc:title>Master Services Agreement</dc:title>
+            <dc:creator>Ada Lovelace</dc:creator>
+            <cp:lastModifiedBy>Charles Babbage</cp:lastModifiedBy>
+            <cp:revision>7</cp:revision>
+            <dcterms:created xsi:type="dcterms:W3CDTF">2024-03-04T14:00:00Z</dcterms:created>
+            <dcterms:modified xsi:type="dcterms:W3CDTF">2024-03-05T09:30:00Z</dcterms:modified>
+        </cp:coreProperties>"#;
+
+    const APP: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+        <Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">
+            <Template>Normal.dotm</Template><TotalTime>42</TotalTime><Pages>3</Pages><Words>1250</Words>
+            <Application>Microsoft Office Word</Application>
+            <HeadingPairs><vt:vector size="2" baseType="variant"><vt:variant><vt:lpstr>Title</vt:lpstr></vt:variant><vt:variant><vt:i4>1</vt:i4></vt:variant></vt:vector></HeadingPairs>
+            <Company>ACME Corp</Company>
+        </Properties>"#;
+
+    const CUSTOM: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+        <Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/custom-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">
+            <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="2" name="Retention"><vt:lpwstr>7 years</vt:lpwstr></property>
+            <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="3" name="Confidential"><vt:bool>true</vt:bool></property>
+        </Properties>"#;
+
+    #[test]
+    fn test_read_metadata() {
+        let data = docx(
+            &paragraph("Body"),
+            &[
+                ("docProps/core.xml", CORE),
+                ("docProps/app.xml", APP),
+                ("docProps/custom.xml", CUSTOM),
+            ],
+        );
+
+        let metadata = OoxmlMetadata::read(&data).unwrap();
+
+        assert_eq!(
+            metadata,
+            OoxmlMetadata {
+                title: Some("Master Services Agreement".to_string()),
+                creator: Some("Ada Lovelace".to_string()),
+                last_modified_by: Some("Charles Babbage".to_string()),
+                revision: Some(7),
+                created: Some(datetime!(2024-03-04 14:00:00 UTC)),
+                modified: Some(datetime!(2024-03-05 09:30:00 UTC)),
+                application: Some("Microsoft Office Word".to_string()),
+                company: Some("ACME Corp".to_string()),
+                template: Some("Normal.dotm".to_string()),
+                pages: Some(3),
+                words: Some(1250),
+                total_time: Some(42),
+                custom: vec![
+                    ("Retention".to_string(), "7 years".to_string()),
+                    ("Confidential".to_string(), "true".to_string()),
+                ],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_properties() {
+        let data = docx(&paragraph("Body"), &[]);
+
+        assert_eq!(
+            OoxmlMetadata::read(&data).unwrap(),
+            OoxmlMetadata::default()
+        );
+    }
+}
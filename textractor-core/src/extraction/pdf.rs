@@ -0,0 +1,591 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::{Context, Result};
+use lopdf::{Document, Object, ObjectId};
+
+use super::boilerplate::{self, BoilerplateLine};
+use super::Extract;
+use crate::errors::ExtractionError;
+
+mod annotations;
+mod attachments;
+mod cleanup;
+mod collector;
+mod forms;
+mod images;
+mod layout;
+mod metadata;
+mod objects;
+mod outline;
+mod recovery;
+mod security;
+mod tables;
+#[cfg(test)]
+mod testing;
+
+pub use annotations::{Annotation, AnnotationKind};
+pub use attachments::PdfAttachment;
+pub use cleanup::PdfCleanup;
+use collector::{Glyph, PageCollector};
+pub use forms::{FormField, FormFieldKind};
+pub use layout::PdfLayout;
+pub use metadata::PdfMetadata;
+pub use outline::OutlineItem;
+pub use tables::PdfTable;
+
+pub struct PdfExtractor;
+
+/// Options for PDF extraction.
+#[derive(Debug, Clone, Default)]
+pub struct PdfOptions {
+    /// Only extract the pages in this 1-based, inclusive range. Pages outside the document are
+    /// ignored.
+    pub pages: Option<RangeInclusive<u32>>,
+    /// User or owner password for encrypted documents.
+    pub password: Option<String>,
+    /// Read the values of AcroForm (or XFA) form fields, and append them to the text.
+    pub include_forms: bool,
+    /// Read comments and markup annotations of each page, and append them to the page text.
+    pub include_annotations: bool,
+    ///
+    /// Extract the text of embedded files (attachments and the documents of a portfolio), and
+    /// append it labelled by file name.
+    pub include_attachments: bool,
+    /// Insert the titles of outline entries as Markdown-style headings (`## Title`) into the
+    /// text of the pages they point to. With a layout other than [`PdfLayout::Stream`], headings
+    /// are placed at the top of their page.
+    pub insert_headings: bool,
+    /// How the text of each page is assembled from its glyphs.
+    pub layout: PdfLayout,
+    /// Post-processing of the page text: ligatures, hyphenation and spacing. Off by default.
+    pub cleanup: PdfCleanup,
+    ///
+    /// Detect tables from ruling lines and text alignment. Tables are returned in
+    /// [`PdfPage::tables`], and replace their text in the page text as Markdown tables.
+    pub extract_tables: bool,
+    ///
+    /// Remove running headers, footers and page numbers: lines repeated at the top or bottom of
+    /// many pages. The removed lines are reported in [`PdfDocument::boilerplate`].
+    pub remove_boilerplate: bool,
+}
+
+/// The text of a single PDF page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfPage {
+    /// 1-based page number in the original document.
+    pub number: u32,
+    pub text: String,
+    /// Annotations, when [`PdfOptions::include_annotations`] is set.
+    pub annotations: Vec<Annotation>,
+    ///
+    /// Whether the page has no text layer but is mostly covered by images, as scanned pages are.
+    /// Its content can only be recovered with OCR.
+    pub image_only: bool,
+    /// Tables, when [`PdfOptions::extract_tables`] is set.
+    pub tables: Vec<PdfTable>,
+}
+
+/// The result of a per-page PDF extraction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfDocument {
+    /// Number of pages in the whole document, regardless of any page range.
+    pub page_count: u32,
+    pub pages: Vec<PdfPage>,
+    pub metadata: PdfMetadata,
+    /// Form fields, when [`PdfOptions::include_forms`] is set.
+    pub form_fields: Vec<FormField>,
+    /// Embedded files, when [`PdfOptions::include_attachments`] is set.
+    pub attachments: Vec<PdfAttachment>,
+    /// The document outline (bookmarks), as a tree.
+    pub outline: Vec<OutlineItem>,
+    /// Lines removed from the pages, when [`PdfOptions::remove_boilerplate`] is set.
+    pub boilerplate: Vec<BoilerplateLine>,
+}
+
+impl PdfDocument {
+    /// Numbers of the extracted pages that have no text layer, see [`PdfPage::image_only`].
+    pub fn image_only_pages(&self) -> Vec<u32> {
+        self.pages
+            .iter()
+            .filter(|page| page.image_only)
+            .map(|page| page.number)
+            .collect()
+    }
+
+    ///
+    /// The text of all extracted pages, in page order, each followed by its annotations, then
+    /// the form field values and the text of attachments.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for page in &self.pages {
+            text.push_str(&page.text);
+            if !page.annotations.is_empty() {
+                text.push_str("\n\n");
+                for annotation in &page.annotations {
+                    text.push_str(&annotation.text());
+                    text.push('\n');
+                }
+            }
+        }
+        let fields: Vec<String> = self
+            .form_fields
+            .iter()
+            .filter_map(FormField::text)
+            .collect();
+        if !fields.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&fields.join("\n"));
+            text.push('\n');
+        }
+        for attachment in self
+            .attachments
+            .iter()
+            .filter_map(PdfAttachment::labelled_text)
+        {
+            text.push_str("\n\n");
+            text.push_str(&attachment);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Attributes a page can inherit from its ancestors in the page tree.
+const INHERITABLE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+fn inherited(document: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+    let mut dict = document.get_dictionary(page_id).ok()?;
+    // a node that is its own ancestor would have us go round in circles
+    let mut seen = HashSet::from([page_id]);
+    loop {
+        if let Ok(value) = dict.get(key) {
+            return Some(value.clone());
+        }
+        let parent = dict.get(b"Parent").and_then(Object::as_reference).ok()?;
+        if !seen.insert(parent) {
+            return None;
+        }
+        dict = document.get_dictionary(parent).ok()?;
+    }
+}
+
+///
+/// Rewrites the page tree so that it only contains `kept` pages, directly under the root `Pages`
+/// node. Inherited attributes are copied onto each page first, so they don't get lost when the
+/// intermediate nodes are dropped.
+fn restrict_pages(document: &mut Document, kept: &[ObjectId]) -> Result<()> {
+    let root = document
+        .catalog()?
+        .get(b"Pages")
+        .and_then(Object::as_reference)
+        .context("PDF catalog has no page tree")?;
+
+    for &page_id in kept {
+        let attributes: Vec<(&[u8], Object)> = INHERITABLE
+            .iter()
+            .filter_map(|key| Some((*key, inherited(document, page_id, key)?)))
+            .collect();
+        let page = document.get_object_mut(page_id)?.as_dict_mut()?;
+        for (key, value) in attributes {
+            page.set(key, value);
+        }
+        page.set("Parent", root);
+    }
+
+    let pages = document.get_object_mut(root)?.as_dict_mut()?;
+    pages.set(
+        "Kids",
+        kept.iter()
+            .map(|id| Object::Reference(*id))
+            .collect::<Vec<_>>(),
+    );
+    pages.set("Count", kept.len() as i64);
+    Ok(())
+}
+
+/// The message of a caught panic, which is a `&str` or a `String` when raised by `panic!`.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "the PDF parser panicked".to_string(),
+        },
+    }
+}
+
+impl PdfExtractor {
+    ///
+    /// Extracts the text of each page of a PDF, optionally limited to a page range. Malformed
+    /// documents that make the PDF parser panic return an
+    /// [`ExtractionError::CorruptDocument`] instead.
+    pub fn extract_document(data: &[u8], options: &PdfOptions) -> Result<PdfDocument> {
+        Self::extract_nested(data, options, 0)
+    }
+
+    /// [`PdfExtractor::extract_document`] for a PDF embedded `depth` levels deep in another.
+    fn extract_nested(data: &[u8], options: &PdfOptions, depth: usize) -> Result<PdfDocument> {
+        // pdf_extract asserts and indexes its way through the document, so malformed input panics
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            Self::read_document(data, options, depth)
+        }))
+        .unwrap_or_else(|payload| {
+            Err(ExtractionError::CorruptDocument(panic_message(payload)).into())
+        })
+    }
+
+    fn read_document(data: &[u8], options: &PdfOptions, depth: usize) -> Result<PdfDocument> {
+        let mut document = recovery::load(data)?;
+        security::decrypt(&mut document, data, options.password.as_deref())?;
+
+        let metadata = metadata::read_metadata(&document);
+        let form_fields = match options.include_forms {
+            true => forms::read_form_fields(&document),
+            false => Vec::new(),
+        };
+        let attachments = match options.include_attachments {
+            true => attachments::read_attachments(&document, depth),
+            false => Vec::new(),
+        };
+        let outline = outline::read_outline(&document);
+        let all_pages = document.get_pages();
+        let page_count = all_pages.len() as u32;
+        let selected: Vec<(u32, ObjectId)> = all_pages
+            .into_iter()
+            .filter(|(number, _)| options.pages.as_ref().is_none_or(|r| r.contains(number)))
+            .collect();
+
+        if selected.is_empty() {
+            return Ok(PdfDocument {
+                page_count,
+                pages: Vec::new(),
+                metadata,
+                form_fields,
+                attachments,
+                outline,
+                boilerplate: Vec::new(),
+            });
+        }
+        if selected.len() as u32 != page_count {
+            let ids: Vec<ObjectId> = selected.iter().map(|(_, id)| *id).collect();
+            restrict_pages(&mut document, &ids)?;
+        }
+
+        let mut collector = PageCollector::new(selected.iter().map(|(n, _)| *n).collect());
+        pdf_extract::output_doc(&document, &mut collector)?;
+
+        let page_ids: HashMap<u32, ObjectId> = selected.into_iter().collect();
+        let mut pages: Vec<PdfPage> = collector
+            .pages
+            .into_iter()
+            .map(|page| {
+                let headings = match options.insert_headings {
+                    true => outline::page_headings(&outline, page.number),
+                    false => Vec::new(),
+                };
+                let tables = match options.extract_tables {
+                    true => tables::detect_tables(&page.glyphs, &page.rulings),
+                    false => Vec::new(),
+                };
+                let remaining: Vec<Glyph>;
+                let glyphs = match tables.is_empty() {
+                    true => &page.glyphs,
+                    false => {
+                        remaining = page
+                            .glyphs
+                            .iter()
+                            .filter(|glyph| !tables.iter().any(|table| table.contains(glyph)))
+                            .cloned()
+                            .collect();
+                        &remaining
+                    }
+                };
+                let markdown = |table: &tables::TableRegion| table.table.to_markdown() + "\n";
+
+                // headings and tables can only be positioned within the page in content stream
+                // order, other layouts get headings at the top and tables at the bottom
+                let text = match options.layout {
+                    PdfLayout::Stream => {
+                        let mut blocks = outline::heading_blocks(&headings, page.height);
+                        blocks.extend(
+                            tables
+                                .iter()
+                                .map(|table| (Some(table.top), markdown(table))),
+                        );
+                        collector::flow_text_with_blocks(glyphs, &blocks)
+                    }
+                    layout => {
+                        let mut text: String = headings
+                            .iter()
+                            .map(|item| outline::heading_text(item))
+                            .collect();
+                        text.push_str(&layout::layout_text(glyphs, layout, page.width));
+                        for table in &tables {
+                            text.push_str(&markdown(table));
+                        }
+                        text
+                    }
+                };
+                let has_text = page
+                    .glyphs
+                    .iter()
+                    .any(|glyph| !glyph.text.trim().is_empty());
+                let image_only = !has_text
+                    && page_ids.get(&page.number).is_some_and(|id| {
+                        images::image_coverage(&document, *id) >= images::SCANNED_COVERAGE
+                    });
+                PdfPage {
+                    number: page.number,
+                    text,
+                    image_only,
+                    tables: tables.into_iter().map(|table| table.table).collect(),
+                    annotations: match (options.include_annotations, page_ids.get(&page.number)) {
+                        (true, Some(id)) => {
+                            annotations::read_annotations(&document, *id, &page.glyphs, page.height)
+                        }
+                        _ => Vec::new(),
+                    },
+                }
+            })
+            .collect();
+
+        let boilerplate = match options.remove_boilerplate {
+            true => boilerplate::remove_boilerplate(
+                &mut pages
+                    .iter_mut()
+                    .map(|page| (page.number, &mut page.text))
+                    .collect::<Vec<_>>(),
+            ),
+            false => Vec::new(),
+        };
+        let vocabulary = cleanup::vocabulary(pages.iter().map(|page| page.text.as_str()));
+        let spacing = options.layout != PdfLayout::Physical;
+        for page in &mut pages {
+            page.text = cleanup::clean_text(&page.text, &options.cleanup, spacing, &vocabulary);
+        }
+
+        Ok(PdfDocument {
+            page_count,
+            pages,
+            metadata,
+            form_fields,
+            attachments,
+            outline,
+            boilerplate,
+        })
+    }
+}
+
+impl Extract for PdfExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        Ok(Self::extract_document(data, &PdfOptions::default())?.text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use time::macros::datetime;
+
+    const BITCOIN: &[u8] = include_bytes!("../../tests/data/bitcoin.pdf");
+    const FELTY: &[u8] = include_bytes!("../../tests/data/felty.pdf");
+    const RC4: &[u8] = include_bytes!("../../tests/data/encrypted_rc4.pdf");
+    const AES128: &[u8] = include_bytes!("../../tests/data/encrypted_aes128.pdf");
+    const AES256: &[u8] = include_bytes!("../../tests/data/encrypted_aes256.pdf");
+
+    fn with_password(password: &str) -> PdfOptions {
+        PdfOptions {
+            password: Some(password.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_extract_pages() {
+        let document = PdfExtractor::extract_document(BITCOIN, &PdfOptions::default()).unwrap();
+
+        assert_eq!(document.page_count, 9);
+        assert_eq!(
+            document.pages.iter().map(|p| p.number).collect::<Vec<_>>(),
+            (1..=9).collect::<Vec<_>>()
+        );
+        assert!(document.pages[0]
+            .text
+            .contains("Bitcoin: A Peer-to-Peer Electronic Cash System"));
+        assert!(document.pages[8].text.contains("References"));
+    }
+
+    #[test]
+    fn test_extract_page_range() {
+        let full = PdfExtractor::extract_document(BITCOIN, &PdfOptions::default()).unwrap();
+        let options = PdfOptions {
+            pages: Some(2..=3),
+            ..Default::default()
+        };
+
+        let document = PdfExtractor::extract_document(BITCOIN, &options).unwrap();
+
+        assert_eq!(document.page_count, 9);
+        assert_eq!(document.pages, full.pages[1..3]);
+    }
+
+    #[test]
+    fn test_extract_page_range_outside_document() {
+        let options = PdfOptions {
+            pages: Some(20..=30),
+            ..Default::default()
+        };
+
+        let document = PdfExtractor::extract_document(BITCOIN, &options).unwrap();
+
+        assert_eq!(document.page_count, 9);
+        assert!(document.pages.is_empty());
+    }
+
+    #[test]
+    fn test_extract_page_range_parent_cycle() {
+        let full = PdfExtractor::extract_document(BITCOIN, &PdfOptions::default()).unwrap();
+        let mut document = Document::load_mem(BITCOIN).unwrap();
+        let page = document.get_pages()[&2];
+        document
+            .get_object_mut(page)
+            .and_then(Object::as_dict_mut)
+            .unwrap()
+            .set("Parent", page);
+        let mut data = Vec::new();
+        document.save_to(&mut data).unwrap();
+        let options = PdfOptions {
+            pages: Some(2..=2),
+            ..Default::default()
+        };
+
+        let document = PdfExtractor::extract_document(&data, &options).unwrap();
+
+        assert_eq!(document.pages[0].text, full.pages[1].text);
+    }
+
+    #[test]
+    fn test_extract_metadata() {
+        let bitcoin = PdfExtractor::extract_document(BITCOIN, &PdfOptions::default())
+            .unwrap()
+            .metadata;
+        let felty = PdfExtractor::extract_document(FELTY, &PdfOptions::default())
+            .unwrap()
+            .metadata;
+
+        assert_eq!(bitcoin.creator.as_deref(), Some("Writer"));
+        assert_eq!(bitcoin.producer.as_deref(), Some("OpenOffice.org 2.4"));
+        assert_eq!(
+            bitcoin.creation_date,
+            Some(datetime!(2009-03-24 11:33:15 -6))
+        );
+        assert_eq!(bitcoin.xmp, None);
+
+        assert_eq!(felty.author.as_deref(), Some("Nathan LeRoy"));
+        assert_eq!(felty.creator.as_deref(), Some("Microsoft Word"));
+        assert_eq!(
+            felty.modification_date,
+            Some(datetime!(2024-08-30 16:58:25 UTC))
+        );
+        assert!(felty.xmp.unwrap().contains("<x:xmpmeta"));
+    }
+
+    #[rstest]
+    #[case::rc4(RC4)]
+    #[case::aes256(AES256)]
+    fn test_extract_encrypted(#[case] data: &[u8]) {
+        for password in ["user", "owner"] {
+            let document = PdfExtractor::extract_document(data, &with_password(password)).unwrap();
+
+            assert_eq!(document.text().trim(), "Secret text");
+            assert_eq!(
+                document.metadata.title.as_deref(),
+                Some("Encrypted fixture")
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_encrypted_without_user_password() {
+        let text = PdfExtractor::extract(AES128).unwrap();
+
+        assert_eq!(text.trim(), "Secret text");
+    }
+
+    #[rstest]
+    #[case::rc4(RC4)]
+    #[case::aes256(AES256)]
+    fn test_extract_encrypted_password_errors(#[case] data: &[u8]) {
+        let missing = PdfExtractor::extract(data).unwrap_err();
+        let wrong = PdfExtractor::extract_document(data, &with_password("nope")).unwrap_err();
+
+        assert_eq!(
+            missing.downcast_ref::<ExtractionError>(),
+            Some(&ExtractionError::PasswordRequired)
+        );
+        assert_eq!(
+            wrong.downcast_ref::<ExtractionError>(),
+            Some(&ExtractionError::IncorrectPassword)
+        );
+    }
+
+    #[test]
+    fn test_remove_boilerplate() {
+        let bodies = [
+            "Revenue grew",
+            "Costs were flat",
+            "Margins improved",
+            "Outlook",
+        ];
+        let contents: Vec<String> = bodies
+            .iter()
+            .enumerate()
+            .map(|(i, body)| {
+                format!(
+                    "BT /F1 10 Tf 72 760 Td (Quarterly Report) Tj ET \
+                     BT /F1 12 Tf 72 700 Td ({}) Tj ET \
+                     BT /F1 10 Tf 300 40 Td (- {} -) Tj ET",
+                    body,
+                    i + 1
+                )
+            })
+            .collect();
+        let (mut document, _) =
+            testing::document(&contents.iter().map(String::as_str).collect::<Vec<_>>());
+        let options = PdfOptions {
+            remove_boilerplate: true,
+            ..Default::default()
+        };
+        let document =
+            PdfExtractor::extract_document(&testing::save(&mut document), &options).unwrap();
+        let texts: Vec<&str> = document.pages.iter().map(|page| page.text.trim()).collect();
+
+        assert_eq!(texts, bodies);
+        assert_eq!(document.boilerplate.len(), 8);
+        assert_eq!(
+            document.boilerplate[1],
+            BoilerplateLine {
+                page: 1,
+                text: "- 1 -".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_extract_panic_is_corrupt_document() {
+        // pdf_extract panics on a font missing from the page resources
+        let (mut document, _) = testing::document(&["BT /F9 12 Tf 72 720 Td (Lost) Tj ET"]);
+        let error = PdfExtractor::extract(&testing::save(&mut document)).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::CorruptDocument(_))
+        ));
+        assert!(crate::extraction::extract(&testing::save(&mut document)).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use time::OffsetDateTime;
+
+use super::collector::{flow_text, Glyph};
+use super::objects::text_entry;
+use crate::extraction::dates::parse_pdf_date;
+use crate::extraction::{Extract, HtmlExtractor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    /// A sticky note.
+    Text,
+    FreeText,
+    Highlight,
+    Underline,
+    StrikeOut,
+    Squiggly,
+    Ink,
+}
+
+impl AnnotationKind {
+    fn from_subtype(subtype: &[u8]) -> Option<AnnotationKind> {
+        match subtype {
+            b"Text" => Some(AnnotationKind::Text),
+            b"FreeText" => Some(AnnotationKind::FreeText),
+            b"Highlight" => Some(AnnotationKind::Highlight),
+            b"Underline" => Some(AnnotationKind::Underline),
+            b"StrikeOut" => Some(AnnotationKind::StrikeOut),
+            b"Squiggly" => Some(AnnotationKind::Squiggly),
+            b"Ink" => Some(AnnotationKind::Ink),
+            _ => None,
+        }
+    }
+
+    fn is_text_markup(self) -> bool {
+        matches!(
+            self,
+            AnnotationKind::Highlight
+                | AnnotationKind::Underline
+                | AnnotationKind::StrikeOut
+                | AnnotationKind::Squiggly
+        )
+    }
+}
+
+/// A comment or markup annotation on a PDF page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    pub author: Option<String>,
+    /// Last modification date, or creation date when the annotation was never modified.
+    pub date: Option<OffsetDateTime>,
+    pub contents: Option<String>,
+    /// For text markup annotations (highlights...), the page text they cover.
+    pub marked_text: Option<String>,
+    /// Annotations replying to this one (`/IRT`), in page order.
+    pub replies: Vec<Annotation>,
+}
+
+fn format_date(date: &OffsetDateTime) -> String {
+    let offset = date.offset();
+    let zone = if offset.is_utc() {
+        "UTC".to_string()
+    } else {
+        let (hours, minutes, _) = offset.as_hms();
+        format!("{:+03}:{:02}", hours, minutes.abs())
+    };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} {}",
+        date.year(),
+        u8::from(date.month()),
+        date.day(),
+        date.hour(),
+        date.minute(),
+        zone
+    )
+}
+
+impl Annotation {
+    /// Formats the annotation and its replies, one per line, e.g.
+    /// `[Highlight] Ada (2024-03-04 14:00 UTC): "marked text" comment`.
+    pub fn text(&self) -> String {
+        let mut lines = Vec::new();
+        self.push_lines(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn push_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        let label = match depth {
+            0 => format!("{:?}", self.kind),
+            _ => "Reply".to_string(),
+        };
+        let mut line = format!("{}[{}]", "  ".repeat(depth), label);
+        if let Some(author) = &self.author {
+            line.push(' ');
+            line.push_str(author);
+        }
+        if let Some(date) = &self.date {
+            line.push_str(&format!(" ({})", format_date(date)));
+        }
+        let body: Vec<String> = self
+            .marked_text
+            .iter()
+            .map(|text| format!("\"{}\"", text))
+            .chain(self.contents.clone())
+            .collect();
+        if !body.is_empty() {
+            line.push_str(": ");
+            line.push_str(&body.join(" "));
+        }
+        lines.push(line);
+        for reply in &self.replies {
+            reply.push_lines(depth + 1, lines);
+        }
+    }
+}
+
+/// The bounding boxes of the `/QuadPoints` of a markup annotation, in page space.
+fn quads(dict: &Dictionary, height: f64) -> Vec<(f64, f64, f64, f64)> {
+    let points: Vec<f64> = dict
+        .get(b"QuadPoints")
+        .and_then(Object::as_array)
+        .map(|points| {
+            points
+                .iter()
+                .filter_map(|point| point.as_float().ok().map(f64::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    points
+        .chunks_exact(8)
+        .map(|quad| {
+            let xs = quad.iter().step_by(2);
+            let ys = quad.iter().skip(1).step_by(2);
+            let (min_x, max_x) = xs.fold((f64::MAX, f64::MIN), |(a, b), x| (a.min(*x), b.max(*x)));
+            let (min_y, max_y) = ys.fold((f64::MAX, f64::MIN), |(a, b), y| (a.min(*y), b.max(*y)));
+            (min_x, height - max_y, max_x, height - min_y)
+        })
+        .collect()
+}
+
+/// The text of the glyphs whose center lies inside one of the quads.
+fn marked_text(glyphs: &[Glyph], quads: &[(f64, f64, f64, f64)]) -> Option<String> {
+    let covered: Vec<Glyph> = glyphs
+        .iter()
+        .filter(|glyph| {
+            let x = (glyph.x + glyph.end) / 2.;
+            let y = glyph.y - glyph.font_size / 3.;
+            quads.iter().any(|(left, top, right, bottom)| {
+                (*left..=*right).contains(&x) && (*top..=*bottom).contains(&y)
+            })
+        })
+        .cloned()
+        .collect();
+    let text = flow_text(&covered)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn annotation(
+    dict: &Dictionary,
+    kind: AnnotationKind,
+    glyphs: &[Glyph],
+    height: f64,
+) -> Annotation {
+    let contents = text_entry(dict, b"Contents").or_else(|| {
+        let rich_text = text_entry(dict, b"RC")?;
+        let text = HtmlExtractor::extract(rich_text.as_bytes()).ok()?;
+        let text = text.trim().to_string();
+        (!text.is_empty()).then_some(text)
+    });
+    let date = text_entry(dict, b"M")
+        .and_then(|date| parse_pdf_date(&date))
+        .or_else(|| text_entry(dict, b"CreationDate").and_then(|date| parse_pdf_date(&date)));
+
+    Annotation {
+        kind,
+        author: text_entry(dict, b"T"),
+        date,
+        contents,
+        marked_text: match kind.is_text_markup() {
+            true => marked_text(glyphs, &quads(dict, height)),
+            false => None,
+        },
+        replies: Vec::new(),
+    }
+}
+
+///
+/// Reads the supported annotations of a page. Replies are nested under the annotation they
+/// respond to; popups and other annotation types are skipped.
+pub(crate) fn read_annotations(
+    document: &Document,
+    page_id: ObjectId,
+    glyphs: &[Glyph],
+    height: f64,
+) -> Vec<Annotation> {
+    let Ok(page) = document.get_dictionary(page_id) else {
+        return Vec::new();
+    };
+    let annots = match page.get(b"Annots") {
+        Ok(Object::Reference(id)) => document.get_object(*id).and_then(Object::as_array),
+        Ok(other) => other.as_array(),
+        Err(error) => Err(error),
+    };
+
+    // (id, annotation, id of the annotation it replies to)
+    let mut entries: Vec<(Option<ObjectId>, Annotation, Option<ObjectId>)> = Vec::new();
+    for annot in annots.map(Vec::as_slice).unwrap_or_default() {
+        let (id, dict) = match annot {
+            Object::Reference(id) => match document.get_dictionary(*id) {
+                Ok(dict) => (Some(*id), dict),
+                Err(_) => continue,
+            },
+            Object::Dictionary(dict) => (None, dict),
+            _ => continue,
+        };
+        let Some(kind) = dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .ok()
+            .and_then(AnnotationKind::from_subtype)
+        else {
+            continue;
+        };
+        // grouped annotations (RT /Group) belong to their parent rather than replying to it
+        if dict.get(b"RT").and_then(Object::as_name).ok() == Some(b"Group") {
+            continue;
+        }
+        let reply_to = dict.get(b"IRT").and_then(Object::as_reference).ok();
+        entries.push((id, annotation(dict, kind, glyphs, height), reply_to));
+    }
+
+    let ids: HashMap<ObjectId, usize> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (id, _, _))| Some(((*id)?, i)))
+        .collect();
+    let parent: Vec<Option<usize>> = entries
+        .iter()
+        .map(|(_, _, reply_to)| reply_to.and_then(|id| ids.get(&id).copied()))
+        .collect();
+
+    fn build(
+        index: usize,
+        entries: &mut [Option<Annotation>],
+        parent: &[Option<usize>],
+        depth: usize,
+    ) -> Option<Annotation> {
+        let mut annotation = entries[index].take()?;
+        if depth < 32 {
+            for child in (0..parent.len()).filter(|child| parent[*child] == Some(index)) {
+                annotation
+                    .replies
+                    .extend(build(child, entries, parent, depth + 1));
+            }
+        }
+        Some(annotation)
+    }
+
+    let mut annotations: Vec<Option<Annotation>> = entries
+        .into_iter()
+        .map(|(_, annotation, _)| Some(annotation))
+        .collect();
+    let mut roots: Vec<(usize, Annotation)> = (0..parent.len())
+        .filter(|i| parent[*i].is_none())
+        .filter_map(|i| Some((i, build(i, &mut annotations, &parent, 0)?)))
+        .collect();
+    // replies that can't be reached from a root (`/IRT` cycles) are shown at the top level
+    for i in 0..parent.len() {
+        if let Some(annotation) = build(i, &mut annotations, &parent, 0) {
+            roots.push((i, annotation));
+        }
+    }
+    roots.sort_by_key(|(i, _)| *i);
+    roots
+        .into_iter()
+        .map(|(_, annotation)| annotation)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{document, save, string};
+    use crate::extraction::{PdfExtractor, PdfOptions};
+    use lopdf::dictionary;
+    use pretty_assertions::assert_eq;
+
+    fn annotated_document() -> Vec<u8> {
+        let (mut document, pages) =
+            document(&["BT /F1 12 Tf 72 720 Td (Hello highlighted world) Tj ET"]);
+        let highlight = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Highlight",
+            "T" => string("Ada"),
+            "M" => string("D:20240304140000Z"),
+            "Contents" => string("Check this"),
+            "QuadPoints" => vec![
+                100.into(), 731.into(), 162.into(), 731.into(),
+                100.into(), 717.into(), 162.into(), 717.into(),
+            ],
+        });
+        let popup = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Popup",
+            "Parent" => highlight,
+        });
+        let reply = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Text",
+            "T" => string("Grace"),
+            "Contents" => string("Agreed"),
+            "IRT" => highlight,
+        });
+        let ink = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Ink",
+            "T" => string("Alan"),
+        });
+        let link = document.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Link" });
+        document
+            .get_object_mut(pages[0])
+            .and_then(Object::as_dict_mut)
+            .unwrap()
+            .set(
+                "Annots",
+                vec![
+                    highlight.into(),
+                    popup.into(),
+                    reply.into(),
+                    ink.into(),
+                    link.into(),
+                ],
+            );
+        save(&mut document)
+    }
+
+    #[test]
+    fn test_read_annotations() {
+        let options = PdfOptions {
+            include_annotations: true,
+            ..Default::default()
+        };
+        let document = PdfExtractor::extract_document(&annotated_document(), &options).unwrap();
+        let annotations = &document.pages[0].annotations;
+
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].kind, AnnotationKind::Highlight);
+        assert_eq!(annotations[0].marked_text.as_deref(), Some("highlighted"));
+        assert_eq!(annotations[0].replies[0].author.as_deref(), Some("Grace"));
+        assert_eq!(annotations[1].kind, AnnotationKind::Ink);
+        assert!(document.text().ends_with(
+            "[Highlight] Ada (2024-03-04 14:00 UTC): \"highlighted\" Check this\n  [Reply] Grace: Agreed\n[Ink] Alan\n"
+        ));
+    }
+
+    #[test]
+    fn test_reply_cycle() {
+        let (mut document, pages) = document(&["BT /F1 12 Tf 72 720 Td (Hello) Tj ET"]);
+        let first = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Text",
+            "Contents" => string("First"),
+        });
+        let second = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Text",
+            "Contents" => string("Second"),
+            "IRT" => first,
+        });
+        for (id, key, value) in [
+            (first, "IRT", Object::from(second)),
+            (pages[0], "Annots", vec![first.into(), second.into()].into()),
+        ] {
+            document
+                .get_object_mut(id)
+                .and_then(Object::as_dict_mut)
+                .unwrap()
+                .set(key, value);
+        }
+        let options = PdfOptions {
+            include_annotations: true,
+            ..Default::default()
+        };
+
+        let document = PdfExtractor::extract_document(&save(&mut document), &options).unwrap();
+        let annotations = &document.pages[0].annotations;
+
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].contents.as_deref(), Some("First"));
+        assert_eq!(
+            annotations[0].replies[0].contents.as_deref(),
+            Some("Second")
+        );
+    }
+
+    #[test]
+    fn test_annotations_are_opt_in() {
+        let document =
+            PdfExtractor::extract_document(&annotated_document(), &PdfOptions::default()).unwrap();
+
+        assert!(document.pages[0].annotations.is_empty());
+        assert_eq!(document.text().trim(), "Hello highlighted world");
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::metadata::decode_text_string;
+use super::objects::{name_tree_entries, resolve, text_entry};
+use super::{PdfExtractor, PdfOptions};
+use crate::detection::ContentType;
+
+/// A file embedded in a PDF, as an attachment or a document of a portfolio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfAttachment {
+    pub name: String,
+    pub description: Option<String>,
+    /// The extracted text, or `None` when the file type is not supported or extraction failed.
+    pub text: Option<String>,
+    /// Why the text of the attachment could not be extracted.
+    pub error: Option<String>,
+}
+
+impl PdfAttachment {
+    /// The attachment text, preceded by an `[Attachment] name` label line.
+    pub fn labelled_text(&self) -> Option<String> {
+        let text = self.text.as_deref()?.trim();
+        Some(format!("[Attachment] {}\n{}", self.name, text))
+    }
+}
+
+// embedded PDFs are read with their own attachments, limit how deep we follow them
+const MAX_ATTACHMENT_DEPTH: usize = 4;
+
+/// The file specifications of the `/EmbeddedFiles` name tree and of file attachment annotations.
+fn file_specs(document: &Document) -> Vec<(String, &Dictionary)> {
+    let mut specs = Vec::new();
+    let tree = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| resolve(document, catalog.get(b"Names").ok()?))
+        .and_then(|names| names.as_dict().ok())
+        .and_then(|names| resolve(document, names.get(b"EmbeddedFiles").ok()?))
+        .and_then(|tree| tree.as_dict().ok());
+    if let Some(tree) = tree {
+        for (name, spec) in name_tree_entries(document, tree) {
+            if let Ok(spec) = spec.as_dict() {
+                specs.push((decode_text_string(name), spec));
+            }
+        }
+    }
+
+    for page_id in document.get_pages().into_values() {
+        let annots = document
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|page| resolve(document, page.get(b"Annots").ok()?))
+            .and_then(|annots| annots.as_array().ok());
+        for annot in annots.map(Vec::as_slice).unwrap_or_default() {
+            let Some(annot) = resolve(document, annot).and_then(|a| a.as_dict().ok()) else {
+                continue;
+            };
+            if annot.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"FileAttachment") {
+                continue;
+            }
+            if let Some(spec) = annot
+                .get(b"FS")
+                .ok()
+                .and_then(|spec| resolve(document, spec))
+                .and_then(|spec| spec.as_dict().ok())
+            {
+                specs.push((String::new(), spec));
+            }
+        }
+    }
+    specs
+}
+
+///
+/// Extracts the text of an embedded file with the extractor matching its content. PDFs embedded
+/// in a document `depth` levels deep are read with their own attachments.
+fn extract_text(data: &[u8], depth: usize) -> anyhow::Result<Option<String>> {
+    match ContentType::from(data) {
+        ContentType::Pdf => {
+            let options = PdfOptions {
+                include_attachments: depth + 1 < MAX_ATTACHMENT_DEPTH,
+                ..Default::default()
+            };
+            Ok(Some(
+                PdfExtractor::extract_nested(data, &options, depth + 1)?.text(),
+            ))
+        }
+        // `extract` reads unknown content as plain text, which for binary files is just noise
+        ContentType::Unknown if std::str::from_utf8(data).is_err() => Ok(None),
+        _ => crate::extraction::extract(data),
+    }
+}
+
+///
+/// Reads the files embedded in the document, through the `/EmbeddedFiles` name tree (which also
+/// holds the documents of a portfolio) and file attachment annotations, and extracts their text.
+/// `depth` is how deep the document is itself embedded in others.
+pub(crate) fn read_attachments(document: &Document, depth: usize) -> Vec<PdfAttachment> {
+    let mut seen: HashSet<ObjectId> = HashSet::new();
+    let mut attachments = Vec::new();
+    for (key, spec) in file_specs(document) {
+        let Some((id, stream)) = spec
+            .get(b"EF")
+            .ok()
+            .and_then(|ef| resolve(document, ef))
+            .and_then(|ef| ef.as_dict().ok())
+            .and_then(|ef| ef.get(b"UF").or_else(|_| ef.get(b"F")).ok())
+            .and_then(|stream| stream.as_reference().ok())
+            .and_then(|id| Some((id, document.get_object(id).ok()?.as_stream().ok()?)))
+        else {
+            continue;
+        };
+        if !seen.insert(id) {
+            continue;
+        }
+
+        let name = text_entry(spec, b"UF")
+            .or_else(|| text_entry(spec, b"F"))
+            .unwrap_or(key);
+        let data = stream
+            .decompressed_content()
+            .unwrap_or_else(|_| stream.content.clone());
+        let (text, error) = match extract_text(&data, depth) {
+            Ok(text) => (text, None),
+            Err(error) => (None, Some(error.to_string())),
+        };
+        attachments.push(PdfAttachment {
+            name,
+            description: text_entry(spec, b"Desc"),
+            text,
+            error,
+        });
+    }
+    attachments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{catalog, document, save, string};
+    use crate::extraction::Extract;
+    use lopdf::{dictionary, Stream};
+    use pretty_assertions::assert_eq;
+
+    fn embed(document: &mut Document, name: &str, data: Vec<u8>) -> ObjectId {
+        let file = document.add_object(Stream::new(dictionary! { "Type" => "EmbeddedFile" }, data));
+        document.add_object(dictionary! {
+            "Type" => "Filespec",
+            "F" => string(name),
+            "UF" => string(name),
+            "EF" => dictionary! { "F" => file },
+        })
+    }
+
+    fn portfolio() -> Vec<u8> {
+        let (mut exhibit, _) = document(&["BT /F1 12 Tf 72 720 Td (Signed contract) Tj ET"]);
+        let exhibit = save(&mut exhibit);
+
+        let (mut document, pages) = document(&["BT /F1 12 Tf 72 720 Td (Cover sheet) Tj ET"]);
+        let notes = embed(&mut document, "notes.txt", b"Witness statement".to_vec());
+        let contract = embed(&mut document, "contract.pdf", exhibit);
+        let image = embed(
+            &mut document,
+            "scan.png",
+            b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec(),
+        );
+        let note = embed(&mut document, "note.txt", b"Attached to page one".to_vec());
+        let annotation = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "FileAttachment",
+            "FS" => note,
+        });
+        document
+            .get_object_mut(pages[0])
+            .and_then(Object::as_dict_mut)
+            .unwrap()
+            .set("Annots", vec![annotation.into()]);
+
+        let names = dictionary! {
+            "EmbeddedFiles" => dictionary! {
+                "Kids" => vec![
+                    Object::Dictionary(dictionary! {
+                        "Names" => vec![string("1"), notes.into(), string("2"), contract.into()],
+                    }),
+                    Object::Dictionary(dictionary! {
+                        "Names" => vec![string("3"), image.into()],
+                    }),
+                ],
+            },
+        };
+        let catalog = catalog(&mut document);
+        catalog.set("Names", names);
+        catalog.set("Collection", dictionary! { "View" => "D" });
+        save(&mut document)
+    }
+
+    fn with_attachments() -> PdfOptions {
+        PdfOptions {
+            include_attachments: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_read_attachments() {
+        let document = PdfExtractor::extract_document(&portfolio(), &with_attachments()).unwrap();
+        let names: Vec<&str> = document
+            .attachments
+            .iter()
+            .map(|attachment| attachment.name.as_str())
+            .collect();
+
+        assert_eq!(
+            names,
+            vec!["notes.txt", "contract.pdf", "scan.png", "note.txt"]
+        );
+        assert_eq!(
+            document.attachments[0].text.as_deref(),
+            Some("Witness statement")
+        );
+        assert_eq!(
+            document.attachments[1].text.as_deref().map(str::trim),
+            Some("Signed contract")
+        );
+        assert_eq!(document.attachments[2].text, None);
+    }
+
+    #[test]
+    fn test_text_includes_attachments() {
+        let text = PdfExtractor::extract_document(&portfolio(), &with_attachments())
+            .unwrap()
+            .text();
+
+        assert!(text.contains("Cover sheet"));
+        assert!(text.contains("[Attachment] notes.txt\nWitness statement\n"));
+        assert!(text.contains("[Attachment] contract.pdf\nSigned contract\n"));
+        assert!(text.contains("[Attachment] note.txt\nAttached to page one\n"));
+        // attachments are opt-in
+        assert!(!PdfExtractor::extract(&portfolio())
+            .unwrap()
+            .contains("[Attachment]"));
+    }
+
+    #[test]
+    fn test_nested_attachments() {
+        let (mut innermost, _) = document(&["BT /F1 12 Tf 72 720 Td (Level 0) Tj ET"]);
+        let mut data = save(&mut innermost);
+        for level in 1..=MAX_ATTACHMENT_DEPTH + 1 {
+            let content = format!("BT /F1 12 Tf 72 720 Td (Level {level}) Tj ET");
+            let (mut document, _) = document(&[content.as_str()]);
+            let file = embed(&mut document, &format!("level{}.pdf", level - 1), data);
+            catalog(&mut document).set(
+                "Names",
+                dictionary! {
+                    "EmbeddedFiles" => dictionary! { "Names" => vec![string("1"), file.into()] },
+                },
+            );
+            data = save(&mut document);
+        }
+
+        let text = PdfExtractor::extract_document(&data, &with_attachments())
+            .unwrap()
+            .text();
+
+        assert!(text.contains("Level 5"));
+        assert!(text.contains("[Attachment] level3.pdf\n"));
+        assert!(text.contains("Level 1"));
+        // the attachments of the fourth nested document are not read
+        assert!(!text.contains("Level 0"));
+    }
+}
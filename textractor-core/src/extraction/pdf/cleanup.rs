@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+
+/// Post-processing applied to the text of PDF pages. Nothing is changed by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PdfCleanup {
+    /// Expand typographic ligatures (`ﬁ`, `ﬂ`, `ﬃ`...) into their letters.
+    pub ligatures: bool,
+    /// Remove soft hyphens (U+00AD), joining the words they split across lines.
+    pub soft_hyphens: bool,
+    /// Rejoin words hyphenated at the end of a line.
+    pub dehyphenate: bool,
+    ///
+    /// Collapse runs of spaces, drop spaces before punctuation and at the end of lines. Not
+    /// applied with [`PdfLayout::Physical`](super::PdfLayout::Physical), which relies on spacing.
+    pub spacing: bool,
+}
+
+impl PdfCleanup {
+    /// Every cleanup step: ligatures, soft hyphens, hyphenation and spacing.
+    pub fn all() -> PdfCleanup {
+        PdfCleanup {
+            ligatures: true,
+            soft_hyphens: true,
+            dehyphenate: true,
+            spacing: true,
+        }
+    }
+}
+
+const SOFT_HYPHEN: char = '\u{ad}';
+
+// prefixes that usually form hyphenated compounds ("high-titer") rather than being split syllables
+const COMPOUND_PREFIXES: [&str; 14] = [
+    "all", "cross", "ex", "full", "half", "high", "long", "low", "non", "part", "self", "short",
+    "side", "well",
+];
+
+fn expand_ligatures(text: &str) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{fb00}' => expanded.push_str("ff"),
+            '\u{fb01}' => expanded.push_str("fi"),
+            '\u{fb02}' => expanded.push_str("fl"),
+            '\u{fb03}' => expanded.push_str("ffi"),
+            '\u{fb04}' => expanded.push_str("ffl"),
+            '\u{fb05}' | '\u{fb06}' => expanded.push_str("st"),
+            c => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+fn normalize_spacing(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let mut normalized = indent.to_string();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        // an ellipsis ("Item ...") is a word of its own
+        let repeated = chars.peek() == Some(&c);
+        match c {
+            ' ' if normalized.ends_with(' ') => {}
+            ',' | '.' | ';' | ':' | '!' | '?' | ')' if normalized.ends_with(' ') && !repeated => {
+                normalized.pop();
+                normalized.push(c);
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+fn vocabulary_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '-')
+        .to_lowercase()
+}
+
+/// The words of all pages, used to decide whether a hyphen at a line break belongs to the word.
+pub(crate) fn vocabulary<'a>(texts: impl Iterator<Item = &'a str>) -> HashSet<String> {
+    texts
+        .flat_map(str::split_whitespace)
+        .map(vocabulary_word)
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+///
+/// Whether the hyphen between `prefix` and the lowercase `suffix` should be kept when joining
+/// them.
+fn keeps_hyphen(prefix: &str, suffix: &str, vocabulary: &HashSet<String>) -> bool {
+    let prefix = prefix.to_lowercase();
+    if vocabulary.contains(&format!("{}{}", prefix, suffix)) {
+        return false;
+    }
+    // a continuation with its own hyphen ("peer-" + "to-peer") is a compound too
+    suffix.contains('-')
+        || vocabulary.contains(&format!("{}-{}", prefix, suffix))
+        || COMPOUND_PREFIXES.contains(&prefix.as_str())
+}
+
+///
+/// Joins a word split at the end of `lines[index]` with the first word of the next line, when
+/// the line ends with a soft hyphen, or (with `dehyphenate`) with a hyphen following a letter and
+/// the next line starts with a lowercase letter. The joined word stays on the first line.
+fn join_split_word(
+    lines: &mut Vec<String>,
+    index: usize,
+    cleanup: &PdfCleanup,
+    vocabulary: &HashSet<String>,
+) {
+    let Some(next) = lines.get(index + 1) else {
+        return;
+    };
+    let line = lines[index].trim_end();
+    let next = next.trim_start();
+    let Some(first) = next.chars().next() else {
+        return;
+    };
+
+    let (stem, hyphen) = if cleanup.soft_hyphens && line.ends_with(SOFT_HYPHEN) {
+        (&line[..line.len() - SOFT_HYPHEN.len_utf8()], None)
+    } else if cleanup.dehyphenate && line.ends_with('-') && first.is_lowercase() {
+        let stem = &line[..line.len() - 1];
+        let prefix_start = stem
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphabetic())
+            .last()
+            .map_or(stem.len(), |(i, _)| i);
+        let prefix = &stem[prefix_start..];
+        if prefix.is_empty() {
+            return;
+        }
+        let suffix = vocabulary_word(next.split_whitespace().next().unwrap_or_default());
+        (stem, Some(keeps_hyphen(prefix, &suffix, vocabulary)))
+    } else {
+        return;
+    };
+
+    let end = next.find(char::is_whitespace).unwrap_or(next.len());
+    let (word, rest) = next.split_at(end);
+    let mut joined = stem.to_string();
+    if hyphen == Some(true) {
+        joined.push('-');
+    }
+    joined.push_str(word);
+    let rest = rest.trim_start().to_string();
+
+    lines[index] = joined;
+    if rest.is_empty() {
+        lines.remove(index + 1);
+    } else {
+        lines[index + 1] = rest;
+    }
+}
+
+/// Applies the cleanup steps to the text of a page.
+pub(crate) fn clean_text(
+    text: &str,
+    cleanup: &PdfCleanup,
+    spacing: bool,
+    vocabulary: &HashSet<String>,
+) -> String {
+    let text = match cleanup.ligatures {
+        true => expand_ligatures(text),
+        false => text.to_string(),
+    };
+
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    if cleanup.soft_hyphens || cleanup.dehyphenate {
+        let mut index = 0;
+        while index < lines.len() {
+            join_split_word(&mut lines, index, cleanup, vocabulary);
+            index += 1;
+        }
+    }
+    if cleanup.soft_hyphens {
+        for line in &mut lines {
+            line.retain(|c| c != SOFT_HYPHEN);
+        }
+    }
+    if cleanup.spacing && spacing {
+        for line in &mut lines {
+            *line = normalize_spacing(line);
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn clean(text: &str) -> String {
+        clean_text(
+            text,
+            &PdfCleanup::all(),
+            true,
+            &vocabulary([text].into_iter()),
+        )
+    }
+
+    #[test]
+    fn test_ligatures() {
+        assert_eq!(clean("\u{fb01}nal e\u{fb03}cient"), "final efficient");
+    }
+
+    #[test]
+    fn test_dehyphenate() {
+        assert_eq!(
+            clean("the extrac-\ntion of text\nis done"),
+            "the extraction\nof text\nis done"
+        );
+        // compounds, and words seen with a hyphen elsewhere, keep it
+        assert_eq!(clean("high-\ntiter antibodies"), "high-titer\nantibodies");
+        assert_eq!(
+            clean("a peer-\nto-peer network of peer-to-peer nodes"),
+            "a peer-to-peer\nnetwork of peer-to-peer nodes"
+        );
+        // only lowercase continuations are joined
+        assert_eq!(clean("HLA-\nDR4 positive"), "HLA-\nDR4 positive");
+        assert_eq!(clean("1990-\n2000"), "1990-\n2000");
+    }
+
+    #[test]
+    fn test_soft_hyphens() {
+        assert_eq!(
+            clean("syl\u{ad}\nlable and hy\u{ad}phen"),
+            "syllable\nand hyphen"
+        );
+    }
+
+    #[test]
+    fn test_spacing() {
+        assert_eq!(
+            clean("system   is  secure , really .  \nmore ..."),
+            "system is secure, really.\nmore ..."
+        );
+    }
+
+    #[test]
+    fn test_no_cleanup() {
+        let text = "e\u{fb03}cient extrac-\ntion";
+        let cleaned = clean_text(text, &PdfCleanup::default(), true, &HashSet::new());
+
+        assert_eq!(cleaned, text);
+    }
+}
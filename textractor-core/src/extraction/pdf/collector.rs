@@ -0,0 +1,354 @@
+use pdf_extract::{ColorSpace, MediaBox, OutputDev, OutputError, Path, PathOp, Transform};
+
+/// A single decoded character (or ligature) with its position in top-left origin page space.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Glyph {
+    pub x: f64,
+    pub y: f64,
+    /// Horizontal position right after the glyph, including its advance width.
+    pub end: f64,
+    pub font_size: f64,
+    pub text: String,
+    /// Whether this is the first glyph of a text showing operator (`Tj`, `TJ`, ...).
+    pub word_start: bool,
+}
+
+/// A horizontal or vertical line drawn on the page, in page space, such as a table border.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Ruling {
+    pub horizontal: bool,
+    /// The `y` of a horizontal ruling, or the `x` of a vertical one.
+    pub position: f64,
+    pub start: f64,
+    pub end: f64,
+}
+
+// lines shorter than this are decorations (bullets, underlined letters) rather than rulings
+const MIN_RULING_LENGTH: f64 = 5.;
+// filled rectangles thinner than this are drawn lines
+const MAX_RULING_THICKNESS: f64 = 3.;
+
+/// Everything collected from one page's content stream.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PageContent {
+    pub number: u32,
+    /// Height used to flip PDF user space (bottom-left origin) into page space.
+    pub height: f64,
+    pub width: f64,
+    pub glyphs: Vec<Glyph>,
+    pub rulings: Vec<Ruling>,
+}
+
+///
+/// An `OutputDev` that records positioned glyphs per page instead of writing text directly,
+/// so that the text of each page can be assembled separately.
+pub(crate) struct PageCollector {
+    pub pages: Vec<PageContent>,
+    /// Maps the position of a processed page to its number in the original document.
+    page_numbers: Vec<u32>,
+    flip_ctm: Transform,
+    word_start: bool,
+}
+
+impl PageCollector {
+    pub fn new(page_numbers: Vec<u32>) -> PageCollector {
+        PageCollector {
+            pages: Vec::new(),
+            page_numbers,
+            flip_ctm: Transform::identity(),
+            word_start: false,
+        }
+    }
+}
+
+impl OutputDev for PageCollector {
+    fn begin_page(
+        &mut self,
+        page_num: u32,
+        media_box: &MediaBox,
+        _: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        let number = self
+            .page_numbers
+            .get(page_num as usize - 1)
+            .copied()
+            .unwrap_or(page_num);
+        self.flip_ctm = Transform::row_major(1., 0., 0., -1., 0., media_box.ury - media_box.lly);
+        self.pages.push(PageContent {
+            number,
+            height: media_box.ury - media_box.lly,
+            width: media_box.urx - media_box.llx,
+            glyphs: Vec::new(),
+            rulings: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        let position = trm.post_transform(&self.flip_ctm);
+        // the side of a square with the same area as the transformed font size rectangle
+        let scaled_x = font_size * (trm.m11 + trm.m21);
+        let scaled_y = font_size * (trm.m12 + trm.m22);
+        let font_size = (scaled_x * scaled_y).abs().sqrt();
+        let (x, y) = (position.m31, position.m32);
+
+        if let Some(page) = self.pages.last_mut() {
+            page.glyphs.push(Glyph {
+                x,
+                y,
+                end: x + width * font_size,
+                font_size,
+                text: char.to_string(),
+                word_start: self.word_start,
+            });
+        }
+        self.word_start = false;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        self.word_start = true;
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn stroke(
+        &mut self,
+        ctm: &Transform,
+        _: &ColorSpace,
+        _: &[f64],
+        path: &Path,
+    ) -> Result<(), OutputError> {
+        let transform = ctm.post_transform(&self.flip_ctm);
+        if let Some(page) = self.pages.last_mut() {
+            for (a, b) in segments(path) {
+                page.rulings
+                    .extend(ruling(apply(&transform, a), apply(&transform, b)));
+            }
+        }
+        Ok(())
+    }
+
+    fn fill(
+        &mut self,
+        ctm: &Transform,
+        _: &ColorSpace,
+        _: &[f64],
+        path: &Path,
+    ) -> Result<(), OutputError> {
+        let transform = ctm.post_transform(&self.flip_ctm);
+        if let Some(page) = self.pages.last_mut() {
+            for points in subpaths(path) {
+                let points: Vec<(f64, f64)> =
+                    points.into_iter().map(|p| apply(&transform, p)).collect();
+                page.rulings.extend(thin_rectangle(&points));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn apply(transform: &Transform, (x, y): (f64, f64)) -> (f64, f64) {
+    (
+        x * transform.m11 + y * transform.m21 + transform.m31,
+        x * transform.m12 + y * transform.m22 + transform.m32,
+    )
+}
+
+fn rectangle(x: f64, y: f64, width: f64, height: f64) -> Vec<(f64, f64)> {
+    vec![
+        (x, y),
+        (x + width, y),
+        (x + width, y + height),
+        (x, y + height),
+    ]
+}
+
+/// The points of each subpath, ignoring curves.
+fn subpaths(path: &Path) -> Vec<Vec<(f64, f64)>> {
+    let mut subpaths: Vec<Vec<(f64, f64)>> = Vec::new();
+    for op in &path.ops {
+        match *op {
+            PathOp::MoveTo(x, y) => subpaths.push(vec![(x, y)]),
+            PathOp::LineTo(x, y) | PathOp::CurveTo(_, _, _, _, x, y) => match subpaths.last_mut() {
+                Some(points) => points.push((x, y)),
+                None => subpaths.push(vec![(x, y)]),
+            },
+            PathOp::Rect(x, y, width, height) => subpaths.push(rectangle(x, y, width, height)),
+            PathOp::Close => {}
+        }
+    }
+    subpaths
+}
+
+/// The straight line segments of a path, in user space.
+fn segments(path: &Path) -> Vec<((f64, f64), (f64, f64))> {
+    let mut segments = Vec::new();
+    let mut start = None;
+    let mut current = None;
+    for op in &path.ops {
+        match *op {
+            PathOp::MoveTo(x, y) => {
+                start = Some((x, y));
+                current = Some((x, y));
+            }
+            PathOp::LineTo(x, y) => {
+                if let Some(from) = current {
+                    segments.push((from, (x, y)));
+                }
+                current = Some((x, y));
+            }
+            PathOp::CurveTo(_, _, _, _, x, y) => current = Some((x, y)),
+            PathOp::Rect(x, y, width, height) => {
+                let corners = rectangle(x, y, width, height);
+                for i in 0..4 {
+                    segments.push((corners[i], corners[(i + 1) % 4]));
+                }
+                start = Some((x, y));
+                current = Some((x, y));
+            }
+            PathOp::Close => {
+                if let (Some(from), Some(to)) = (current, start) {
+                    segments.push((from, to));
+                }
+                current = start;
+            }
+        }
+    }
+    segments
+}
+
+/// A ruling for an axis-aligned segment of a reasonable length.
+fn ruling((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> Option<Ruling> {
+    let (dx, dy) = ((x2 - x1).abs(), (y2 - y1).abs());
+    if dy < 1. && dx >= MIN_RULING_LENGTH {
+        Some(Ruling {
+            horizontal: true,
+            position: (y1 + y2) / 2.,
+            start: x1.min(x2),
+            end: x1.max(x2),
+        })
+    } else if dx < 1. && dy >= MIN_RULING_LENGTH {
+        Some(Ruling {
+            horizontal: false,
+            position: (x1 + x2) / 2.,
+            start: y1.min(y2),
+            end: y1.max(y2),
+        })
+    } else {
+        None
+    }
+}
+
+/// A ruling for a filled shape that is a thin line.
+fn thin_rectangle(points: &[(f64, f64)]) -> Option<Ruling> {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = points.iter().copied().unzip();
+    let (left, right) = (
+        xs.iter().copied().fold(f64::MAX, f64::min),
+        xs.iter().copied().fold(f64::MIN, f64::max),
+    );
+    let (top, bottom) = (
+        ys.iter().copied().fold(f64::MAX, f64::min),
+        ys.iter().copied().fold(f64::MIN, f64::max),
+    );
+    if bottom - top <= MAX_RULING_THICKNESS {
+        ruling((left, (top + bottom) / 2.), (right, (top + bottom) / 2.))
+    } else if right - left <= MAX_RULING_THICKNESS {
+        ruling(((left + right) / 2., top), ((left + right) / 2., bottom))
+    } else {
+        None
+    }
+}
+
+///
+/// Assembles glyphs in content stream order, inserting line breaks and spaces using the same
+/// heuristics as `pdf_extract::PlainTextOutput`.
+pub(crate) fn flow_text(glyphs: &[Glyph]) -> String {
+    let mut text = String::new();
+    let mut last_end = 100000.;
+    let mut last_y = 0.;
+
+    for glyph in glyphs {
+        if glyph.word_start {
+            let dy = (glyph.y - last_y).abs();
+            if dy > glyph.font_size * 1.5 {
+                text.push('\n');
+            }
+            // we've moved to the left and down
+            if glyph.x < last_end && dy > glyph.font_size * 0.5 {
+                text.push('\n');
+            }
+            if glyph.x > last_end + glyph.font_size * 0.1 {
+                text.push(' ');
+            }
+        }
+        text.push_str(&glyph.text);
+        last_y = glyph.y;
+        last_end = glyph.end;
+    }
+
+    text
+}
+
+///
+/// Like [`flow_text`], with blocks of text (headings, tables...) inserted before the first glyph
+/// below their vertical position in page space, or at the top of the page without a position.
+/// Each block is separated from the surrounding text by a blank line.
+pub(crate) fn flow_text_with_blocks(glyphs: &[Glyph], blocks: &[(Option<f64>, String)]) -> String {
+    let mut positioned: Vec<(usize, &str)> = blocks
+        .iter()
+        .map(|(y, block)| {
+            let index = match y {
+                // allow for the ascent of the first line below the position
+                Some(y) => glyphs
+                    .iter()
+                    .position(|glyph| glyph.y - glyph.font_size >= y - 1.)
+                    .unwrap_or(glyphs.len()),
+                None => 0,
+            };
+            (index, block.as_str())
+        })
+        .collect();
+    positioned.sort_by_key(|(index, _)| *index);
+
+    // the text before the first block keeps its leading whitespace, like the plain page text
+    let mut text = String::new();
+    let mut start = 0;
+    for (i, (index, block)) in positioned.iter().enumerate() {
+        let segment = flow_text(&glyphs[start..*index]);
+        if i == 0 {
+            text.push_str(segment.trim_end());
+        } else {
+            text.push_str(segment.trim());
+        }
+        if !text.is_empty() && !text.ends_with("\n\n") {
+            text.push_str("\n\n");
+        }
+        text.push_str(block);
+        start = *index;
+    }
+    let rest = flow_text(&glyphs[start..]);
+    text.push_str(match positioned.is_empty() {
+        true => &rest,
+        false => rest.trim_start(),
+    });
+    text
+}
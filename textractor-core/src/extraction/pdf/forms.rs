@@ -0,0 +1,417 @@
+use std::collections::HashSet;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use xml::reader::{EventReader, XmlEvent};
+
+use super::metadata::decode_text_string;
+use super::objects::resolve;
+
+// field flags (Ff) of button fields
+const FLAG_RADIO: i64 = 1 << 15;
+const FLAG_PUSH_BUTTON: i64 = 1 << 16;
+
+const XFA_DATA: &str = "http://www.xfa.org/schema/xfa-data/1.0/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormFieldKind {
+    Text,
+    CheckBox,
+    RadioButton,
+    Choice,
+    Signature,
+    /// A value from the XFA `datasets` packet.
+    Xfa,
+}
+
+/// A filled-in (or empty) PDF form field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormField {
+    /// Fully qualified name, e.g. `applicant.address.city`.
+    pub name: String,
+    pub kind: FormFieldKind,
+    /// The field value; for buttons, the name of the selected state.
+    pub value: Option<String>,
+    /// Whether a check box or radio button is selected.
+    pub checked: Option<bool>,
+}
+
+impl FormField {
+    /// The field as a `name: value` line, or `None` when there is nothing to show.
+    pub fn text(&self) -> Option<String> {
+        match (self.kind, self.checked, &self.value) {
+            (FormFieldKind::CheckBox, Some(checked), _) => Some(format!(
+                "{}: [{}]",
+                self.name,
+                if checked { "x" } else { " " }
+            )),
+            (_, _, Some(value)) => Some(format!("{}: {}", self.name, value)),
+            _ => None,
+        }
+    }
+}
+
+/// Looks up an inheritable field attribute (FT, V, Ff) on the field or its ancestors.
+fn inherited<'a>(document: &'a Document, field: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    let mut field = field;
+    for _ in 0..64 {
+        if let Ok(value) = field.get(key) {
+            return resolve(document, value);
+        }
+        field = field
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .ok()?;
+    }
+    None
+}
+
+fn text_value(document: &Document, value: &Object) -> Option<String> {
+    let text = match resolve(document, value)? {
+        Object::String(bytes, _) => decode_text_string(bytes),
+        Object::Name(name) => String::from_utf8_lossy(name).into_owned(),
+        Object::Array(items) => items
+            .iter()
+            .filter_map(|item| text_value(document, item))
+            .collect::<Vec<_>>()
+            .join(", "),
+        // rich text values are stored as streams
+        Object::Stream(stream) => String::from_utf8_lossy(
+            &stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone()),
+        )
+        .into_owned(),
+        _ => return None,
+    };
+    let text = text.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn terminal_field(document: &Document, name: String, field: &Dictionary) -> Option<FormField> {
+    let field_type = inherited(document, field, b"FT")
+        .and_then(|ft| ft.as_name().ok())
+        .unwrap_or(b"");
+    let flags = inherited(document, field, b"Ff")
+        .and_then(|flags| flags.as_i64().ok())
+        .unwrap_or(0);
+    let value = inherited(document, field, b"V");
+
+    let kind = match field_type {
+        b"Tx" => FormFieldKind::Text,
+        b"Ch" => FormFieldKind::Choice,
+        b"Sig" => FormFieldKind::Signature,
+        b"Btn" if flags & FLAG_PUSH_BUTTON != 0 => return None,
+        b"Btn" if flags & FLAG_RADIO != 0 => FormFieldKind::RadioButton,
+        b"Btn" => FormFieldKind::CheckBox,
+        _ => return None,
+    };
+
+    let (value, checked) = match kind {
+        FormFieldKind::CheckBox | FormFieldKind::RadioButton => {
+            let state = value
+                .and_then(|v| v.as_name().ok())
+                .filter(|state| *state != b"Off");
+            (
+                state.map(|state| String::from_utf8_lossy(state).into_owned()),
+                Some(state.is_some()),
+            )
+        }
+        FormFieldKind::Signature => (None, None),
+        _ => (value.and_then(|v| text_value(document, v)), None),
+    };
+
+    Some(FormField {
+        name,
+        kind,
+        value,
+        checked,
+    })
+}
+
+fn collect_fields(
+    document: &Document,
+    id: Option<ObjectId>,
+    field: &Dictionary,
+    parent_name: &str,
+    seen: &mut HashSet<ObjectId>,
+    fields: &mut Vec<FormField>,
+) {
+    if let Some(id) = id {
+        if !seen.insert(id) {
+            return;
+        }
+    }
+
+    let partial = field
+        .get(b"T")
+        .and_then(Object::as_str)
+        .map(decode_text_string)
+        .ok();
+    let name = match (parent_name, partial) {
+        (_, None) => parent_name.to_string(),
+        ("", Some(partial)) => partial,
+        (parent, Some(partial)) => format!("{}.{}", parent, partial),
+    };
+
+    // kids without a partial name are the widget annotations of this field
+    let kids: Vec<(Option<ObjectId>, &Dictionary)> = field
+        .get(b"Kids")
+        .and_then(Object::as_array)
+        .map(|kids| {
+            kids.iter()
+                .filter_map(|kid| match kid {
+                    Object::Reference(id) => Some((Some(*id), document.get_dictionary(*id).ok()?)),
+                    Object::Dictionary(dict) => Some((None, dict)),
+                    _ => None,
+                })
+                .filter(|(_, kid)| kid.has(b"T"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if kids.is_empty() {
+        fields.extend(terminal_field(document, name, field));
+    } else {
+        for (kid_id, kid) in kids {
+            collect_fields(document, kid_id, kid, &name, seen, fields);
+        }
+    }
+}
+
+/// Concatenates the XFA packets (a single stream or an array of name/stream pairs).
+fn xfa_packet(document: &Document, xfa: &Object) -> Option<String> {
+    let streams: Vec<&Object> = match resolve(document, xfa)? {
+        Object::Array(items) => items.iter().skip(1).step_by(2).collect(),
+        other => vec![other],
+    };
+    let mut packet = Vec::new();
+    for stream in streams {
+        let Some(Object::Stream(stream)) = resolve(document, stream) else {
+            continue;
+        };
+        match stream.decompressed_content() {
+            Ok(content) => packet.extend(content),
+            Err(_) => packet.extend_from_slice(&stream.content),
+        }
+    }
+    Some(String::from_utf8_lossy(&packet).into_owned())
+}
+
+/// Reads the leaf values of the `xfa:data` element of the XFA `datasets` packet.
+fn xfa_fields(packet: &str) -> Vec<FormField> {
+    let mut fields = Vec::new();
+    // element names below xfa:data, or None while outside of it
+    let mut path: Option<Vec<String>> = None;
+    let mut text = String::new();
+
+    for event in EventReader::new(packet.as_bytes()) {
+        match event {
+            Ok(XmlEvent::StartElement { name, .. }) => match &mut path {
+                Some(path) => {
+                    path.push(name.local_name);
+                    text.clear();
+                }
+                None if name.namespace.as_deref() == Some(XFA_DATA)
+                    && name.local_name == "data" =>
+                {
+                    path = Some(Vec::new());
+                }
+                None => {}
+            },
+            Ok(XmlEvent::Characters(chars)) | Ok(XmlEvent::CData(chars)) => {
+                text.push_str(&chars);
+            }
+            Ok(XmlEvent::EndElement { .. }) => match &mut path {
+                Some(elements) if elements.is_empty() => path = None,
+                Some(elements) => {
+                    let value = std::mem::take(&mut text).trim().to_string();
+                    if !value.is_empty() {
+                        fields.push(FormField {
+                            name: elements.join("."),
+                            kind: FormFieldKind::Xfa,
+                            value: Some(value),
+                            checked: None,
+                        });
+                    }
+                    elements.pop();
+                }
+                None => {}
+            },
+            Err(_) => break,
+            _ => {}
+        }
+    }
+    fields
+}
+
+///
+/// Reads the fields of the document's interactive form. The XFA datasets are only used for
+/// pure XFA forms; hybrid forms keep the same values in their AcroForm fields.
+pub(crate) fn read_form_fields(document: &Document) -> Vec<FormField> {
+    let Some(form) = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| resolve(document, form))
+        .and_then(|form| form.as_dict().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut fields = Vec::new();
+    let mut seen = HashSet::new();
+    let roots = form
+        .get(b"Fields")
+        .and_then(Object::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for root in roots {
+        let id = root.as_reference().ok();
+        if let Some(Object::Dictionary(field)) = resolve(document, root) {
+            collect_fields(document, id, field, "", &mut seen, &mut fields);
+        }
+    }
+
+    if fields.is_empty() {
+        if let Some(packet) = form
+            .get(b"XFA")
+            .ok()
+            .and_then(|xfa| xfa_packet(document, xfa))
+        {
+            fields = xfa_fields(&packet);
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{catalog, document, save, string};
+    use crate::extraction::{PdfExtractor, PdfOptions};
+    use lopdf::{dictionary, Stream, StringFormat};
+    use pretty_assertions::assert_eq;
+
+    fn form_document() -> Document {
+        let (mut document, _) = document(&["BT /F1 12 Tf 72 720 Td (Application form) Tj ET"]);
+        let widget = document.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Widget" });
+        let name = document.add_object(dictionary! {
+            "T" => string("name"),
+            "FT" => "Tx",
+            "V" => Object::String(b"\xfe\xff\x00A\x00d\x00a".to_vec(), StringFormat::Hexadecimal),
+            "Kids" => vec![widget.into()],
+        });
+        let applicant = document.add_object(dictionary! {
+            "T" => string("applicant"),
+            "Kids" => vec![name.into()],
+        });
+        let consent = document.add_object(dictionary! {
+            "T" => string("consent"),
+            "FT" => "Btn",
+            "V" => "Yes",
+        });
+        let newsletter = document.add_object(dictionary! {
+            "T" => string("newsletter"),
+            "FT" => "Btn",
+            "V" => "Off",
+        });
+        let plan = document.add_object(dictionary! {
+            "T" => string("plan"),
+            "FT" => "Btn",
+            "Ff" => FLAG_RADIO,
+            "V" => "Premium",
+        });
+        let submit = document.add_object(dictionary! {
+            "T" => string("submit"),
+            "FT" => "Btn",
+            "Ff" => FLAG_PUSH_BUTTON,
+        });
+        let colors = document.add_object(dictionary! {
+            "T" => string("colors"),
+            "FT" => "Ch",
+            "V" => vec![string("red"), string("blue")],
+        });
+        catalog(&mut document).set(
+            "AcroForm",
+            dictionary! {
+                "Fields" => vec![
+                    applicant.into(), consent.into(), newsletter.into(), plan.into(),
+                    submit.into(), colors.into(),
+                ],
+            },
+        );
+        document
+    }
+
+    #[test]
+    fn test_read_acroform_fields() {
+        let fields = read_form_fields(&form_document());
+
+        let summary: Vec<(&str, FormFieldKind, Option<&str>, Option<bool>)> = fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.kind, f.value.as_deref(), f.checked))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("applicant.name", FormFieldKind::Text, Some("Ada"), None),
+                ("consent", FormFieldKind::CheckBox, Some("Yes"), Some(true)),
+                ("newsletter", FormFieldKind::CheckBox, None, Some(false)),
+                (
+                    "plan",
+                    FormFieldKind::RadioButton,
+                    Some("Premium"),
+                    Some(true)
+                ),
+                ("colors", FormFieldKind::Choice, Some("red, blue"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_form_text() {
+        let data = save(&mut form_document());
+        let options = PdfOptions {
+            include_forms: true,
+            ..Default::default()
+        };
+
+        let without = PdfExtractor::extract_document(&data, &PdfOptions::default()).unwrap();
+        let with = PdfExtractor::extract_document(&data, &options).unwrap();
+
+        assert!(without.form_fields.is_empty());
+        assert!(with.text().ends_with(
+            "\n\napplicant.name: Ada\nconsent: [x]\nnewsletter: [ ]\nplan: Premium\ncolors: red, blue\n"
+        ));
+    }
+
+    #[test]
+    fn test_read_xfa_fields() {
+        let (mut document, _) = document(&[""]);
+        let datasets = document.add_object(Stream::new(
+            dictionary! {},
+            br#"<xfa:datasets xmlns:xfa="http://www.xfa.org/schema/xfa-data/1.0/">
+                <xfa:data><form1><employee><name>Grace</name><id>42</id></employee><notes/></form1></xfa:data>
+                </xfa:datasets>"#
+                .to_vec(),
+        ));
+        catalog(&mut document).set(
+            "AcroForm",
+            dictionary! {
+                "Fields" => Vec::<Object>::new(),
+                "XFA" => vec![string("datasets"), datasets.into()],
+            },
+        );
+
+        let fields = read_form_fields(&document);
+
+        assert_eq!(
+            fields
+                .iter()
+                .filter_map(FormField::text)
+                .collect::<Vec<_>>(),
+            ["form1.employee.name: Grace", "form1.employee.id: 42"]
+        );
+    }
+}
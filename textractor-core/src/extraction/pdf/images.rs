@@ -0,0 +1,282 @@
+use std::ops::Range;
+
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::inherited;
+
+/// Pages with no text whose images cover at least this fraction of their area are scans.
+pub(crate) const SCANNED_COVERAGE: f64 = 0.5;
+
+// forms can draw other forms, limit how deep we follow them
+const MAX_FORM_DEPTH: usize = 8;
+
+/// A transformation matrix `[a b c d e f]`, as used by the `cm` operator.
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1., 0., 0., 1., 0., 0.];
+
+fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn matrix(operands: &[Object]) -> Option<Matrix> {
+    let values: Vec<f64> = operands
+        .iter()
+        .filter_map(|value| value.as_float().ok().map(f64::from))
+        .collect();
+    values.try_into().ok()
+}
+
+fn dictionary<'a>(document: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    match object {
+        Object::Reference(id) => document.get_dictionary(*id).ok(),
+        other => other.as_dict().ok(),
+    }
+}
+
+/// The area of the unit square, where images are drawn, in user space.
+fn unit_area(ctm: &Matrix) -> f64 {
+    (ctm[0] * ctm[3] - ctm[1] * ctm[2]).abs()
+}
+
+/// The position of the first `keyword` at or after `from`, delimited by whitespace.
+fn keyword(content: &[u8], keyword: &[u8], from: usize) -> Option<usize> {
+    let delimiter = |i: Option<usize>| {
+        i.and_then(|i| content.get(i))
+            .is_none_or(u8::is_ascii_whitespace)
+    };
+    (from..content.len()).find(|&i| {
+        content[i..].starts_with(keyword)
+            && delimiter(i.checked_sub(1))
+            && delimiter(Some(i + keyword.len()))
+    })
+}
+
+///
+/// The byte range of the first inline image (`BI` dictionary `ID` data `EI`) of a content
+/// stream. lopdf can't parse the binary data of inline images, so they are cut out of the
+/// content before decoding it.
+fn inline_image(content: &[u8]) -> Option<Range<usize>> {
+    let start = keyword(content, b"BI", 0)?;
+    let data = keyword(content, b"ID", start + 2)? + 3;
+    let end = keyword(content, b"EI", data)? + 2;
+    Some(start..end)
+}
+
+/// Sums the area (in user space) of the images drawn by a content stream.
+fn image_area(
+    document: &Document,
+    content: &[u8],
+    resources: Option<&Dictionary>,
+    ctm: Matrix,
+    depth: usize,
+) -> f64 {
+    let xobjects = resources
+        .and_then(|resources| resources.get(b"XObject").ok())
+        .and_then(|xobjects| dictionary(document, xobjects));
+
+    let mut area = 0.;
+    let mut stack = Vec::new();
+    let mut ctm = ctm;
+    let mut rest = content;
+    loop {
+        let inline = inline_image(rest);
+        let operations = &rest[..inline.as_ref().map_or(rest.len(), |image| image.start)];
+        let operations = Content::decode(operations)
+            .map(|content| content.operations)
+            .unwrap_or_default();
+        for operation in &operations {
+            match operation.operator.as_str() {
+                "q" => stack.push(ctm),
+                "Q" => ctm = stack.pop().unwrap_or(ctm),
+                "cm" => {
+                    if let Some(m) = matrix(&operation.operands) {
+                        ctm = multiply(&m, &ctm);
+                    }
+                }
+                "Do" => {
+                    let Some(stream) = operation
+                        .operands
+                        .first()
+                        .and_then(|name| name.as_name().ok())
+                        .and_then(|name| xobjects?.get(name).ok())
+                        .and_then(|xobject| xobject.as_reference().ok())
+                        .and_then(|id| document.get_object(id).ok())
+                        .and_then(|xobject| xobject.as_stream().ok())
+                    else {
+                        continue;
+                    };
+                    match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+                        // images are drawn into the unit square
+                        Ok(b"Image") => area += unit_area(&ctm),
+                        Ok(b"Form") if depth < MAX_FORM_DEPTH => {
+                            let form_matrix = stream
+                                .dict
+                                .get(b"Matrix")
+                                .and_then(Object::as_array)
+                                .ok()
+                                .and_then(|m| matrix(m))
+                                .unwrap_or(IDENTITY);
+                            let form_resources = stream
+                                .dict
+                                .get(b"Resources")
+                                .ok()
+                                .and_then(|r| dictionary(document, r))
+                                .or(resources);
+                            let content = stream
+                                .decompressed_content()
+                                .unwrap_or_else(|_| stream.content.clone());
+                            area += image_area(
+                                document,
+                                &content,
+                                form_resources,
+                                multiply(&form_matrix, &ctm),
+                                depth + 1,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        let Some(image) = inline else {
+            break;
+        };
+        area += unit_area(&ctm);
+        rest = &rest[image.end..];
+    }
+    area
+}
+
+/// The fraction of a page's area covered by images, capped at 1.
+pub(crate) fn image_coverage(document: &Document, page_id: ObjectId) -> f64 {
+    let page_area = inherited(document, page_id, b"MediaBox")
+        .and_then(|media_box| rectangle_area(&media_box))
+        .unwrap_or(612. * 792.);
+    let resources = inherited(document, page_id, b"Resources");
+    let resources = resources
+        .as_ref()
+        .and_then(|resources| dictionary(document, resources));
+    let Ok(content) = document.get_page_content(page_id) else {
+        return 0.;
+    };
+    let area = image_area(document, &content, resources, IDENTITY, 0);
+    (area / page_area).min(1.)
+}
+
+/// The area of a rectangle given as `[llx lly urx ury]`.
+fn rectangle_area(rectangle: &Object) -> Option<f64> {
+    let values: Vec<f64> = rectangle
+        .as_array()
+        .ok()?
+        .iter()
+        .filter_map(|value| value.as_float().ok().map(f64::from))
+        .collect();
+    let [llx, lly, urx, ury]: [f64; 4] = values.try_into().ok()?;
+    let area = ((urx - llx) * (ury - lly)).abs();
+    (area > 0.).then_some(area)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::extraction::pdf::testing::{document, save};
+    use crate::extraction::{PdfExtractor, PdfOptions};
+    use lopdf::{dictionary, Object, Stream};
+
+    /// One page per content stream, each with a 1x1 gray image as `/Im1`.
+    fn with_image(contents: &[&str]) -> Vec<u8> {
+        let (mut document, pages) = document(contents);
+        let image = document.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 1,
+                "Height" => 1,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![0],
+        ));
+        for page in pages {
+            let resources = document
+                .get_object_mut(page)
+                .and_then(Object::as_dict_mut)
+                .and_then(|page| page.get_mut(b"Resources"))
+                .and_then(Object::as_dict_mut)
+                .unwrap();
+            resources.set("XObject", dictionary! { "Im1" => image });
+        }
+        save(&mut document)
+    }
+
+    #[test]
+    fn test_image_only_pages() {
+        let data = with_image(&[
+            // a scan
+            "q 612 0 0 792 0 0 cm /Im1 Do Q",
+            // a scan with an OCR text layer
+            "q 612 0 0 792 0 0 cm /Im1 Do Q BT 3 Tr /F1 12 Tf 72 720 Td (Recognized) Tj ET",
+            // a small picture on an otherwise blank page
+            "q 100 0 0 100 72 600 cm /Im1 Do Q",
+            // a blank page
+            "",
+        ]);
+        let document = PdfExtractor::extract_document(&data, &PdfOptions::default()).unwrap();
+        let image_only: Vec<bool> = document.pages.iter().map(|page| page.image_only).collect();
+
+        assert_eq!(image_only, vec![true, false, false, false]);
+        assert_eq!(document.image_only_pages(), vec![1]);
+    }
+
+    #[test]
+    fn test_inline_image() {
+        let (mut document, _) = document(&[
+            "q 612 0 0 792 0 0 cm BI /W 1 /H 1 /CS /G /BPC 8 ID \u{7f} EI Q",
+            "q 100 0 0 100 72 600 cm BI /W 1 /H 1 /CS /G /BPC 8 ID \u{7f} EI Q",
+        ]);
+
+        let data = save(&mut document);
+        let document = PdfExtractor::extract_document(&data, &PdfOptions::default()).unwrap();
+
+        assert_eq!(document.image_only_pages(), vec![1]);
+    }
+
+    #[test]
+    fn test_image_in_form() {
+        let (mut document, pages) = document(&["/Fm1 Do"]);
+        let image = document.add_object(Stream::new(
+            dictionary! { "Subtype" => "Image", "Width" => 1, "Height" => 1 },
+            vec![0],
+        ));
+        let form = document.add_object(Stream::new(
+            dictionary! {
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 1.into(), 1.into()],
+                "Matrix" => vec![612.into(), 0.into(), 0.into(), 792.into(), 0.into(), 0.into()],
+                "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => image } },
+            },
+            b"/Im1 Do".to_vec(),
+        ));
+        document
+            .get_object_mut(pages[0])
+            .and_then(Object::as_dict_mut)
+            .and_then(|page| page.get_mut(b"Resources"))
+            .and_then(Object::as_dict_mut)
+            .unwrap()
+            .set("XObject", dictionary! { "Fm1" => form });
+
+        let data = save(&mut document);
+        let document = PdfExtractor::extract_document(&data, &PdfOptions::default()).unwrap();
+
+        assert!(document.pages[0].image_only);
+    }
+}
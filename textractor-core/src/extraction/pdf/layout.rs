@@ -0,0 +1,311 @@
+use super::collector::Glyph;
+
+// lines of the physical layout are padded up to this many characters at most
+const MAX_COLUMNS: f64 = 1000.;
+
+/// How the glyphs of a page are assembled into text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PdfLayout {
+    /// Content stream order, as produced by `pdf_extract`.
+    #[default]
+    Stream,
+    /// Lines grouped into columns and blocks, read top to bottom and left to right.
+    ReadingOrder,
+    ///
+    /// Lines as they appear on the page, with horizontal positions kept by padding with spaces
+    /// (similar to `pdftotext -layout`).
+    Physical,
+}
+
+/// Glyphs sharing a baseline and close enough horizontally to be read as one piece of text.
+pub(crate) struct Line<'a> {
+    pub glyphs: Vec<&'a Glyph>,
+    pub left: f64,
+    pub right: f64,
+    pub y: f64,
+    pub font_size: f64,
+}
+
+impl<'a> Line<'a> {
+    fn new(glyph: &'a Glyph) -> Line<'a> {
+        Line {
+            glyphs: vec![glyph],
+            left: glyph.x,
+            right: glyph.end,
+            y: glyph.y,
+            font_size: glyph.font_size,
+        }
+    }
+
+    fn accepts(&self, glyph: &Glyph) -> bool {
+        let font_size = self.font_size.max(glyph.font_size);
+        let gap = glyph.x - self.right;
+        (glyph.y - self.y).abs() <= font_size * 0.4
+            && gap >= -font_size * 0.5
+            && gap <= font_size * 1.5
+    }
+
+    fn push(&mut self, glyph: &'a Glyph) {
+        self.left = self.left.min(glyph.x);
+        self.right = self.right.max(glyph.end);
+        self.font_size = self.font_size.max(glyph.font_size);
+        self.glyphs.push(glyph);
+    }
+
+    fn top(&self) -> f64 {
+        self.y - self.font_size
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        let mut last: Option<&Glyph> = None;
+        for glyph in &self.glyphs {
+            if let Some(last) = last {
+                push_space(&mut text, last, glyph);
+            }
+            text.push_str(&glyph.text);
+            last = Some(glyph);
+        }
+        text.trim().to_string()
+    }
+}
+
+/// Adds a space between two glyphs when they are visibly apart and no whitespace separates them.
+fn push_space(text: &mut String, last: &Glyph, glyph: &Glyph) {
+    let apart = glyph.x > last.end + glyph.font_size * 0.1;
+    let blank = text.ends_with(char::is_whitespace) || glyph.text.starts_with(char::is_whitespace);
+    if apart && !blank {
+        text.push(' ');
+    }
+}
+
+/// Groups glyphs into lines, in content stream order.
+pub(crate) fn lines(glyphs: &[Glyph]) -> Vec<Line<'_>> {
+    let mut lines: Vec<Line> = Vec::new();
+    for glyph in glyphs {
+        if glyph.text.trim().is_empty() && !lines.iter().any(|line| line.accepts(glyph)) {
+            continue;
+        }
+        match lines.iter_mut().rev().find(|line| line.accepts(glyph)) {
+            Some(line) => line.push(glyph),
+            None => lines.push(Line::new(glyph)),
+        }
+    }
+    lines
+}
+
+/// Splits `lines` at the first gap along one axis that no line crosses.
+fn cut<'a>(
+    mut lines: Vec<Line<'a>>,
+    start: fn(&Line) -> f64,
+    end: fn(&Line) -> f64,
+) -> Result<(Vec<Line<'a>>, Vec<Line<'a>>), Vec<Line<'a>>> {
+    lines.sort_by(|a, b| start(a).total_cmp(&start(b)));
+    let mut reach = f64::MIN;
+    let position = lines.iter().enumerate().position(|(i, line)| {
+        let gap = i > 0 && start(line) > reach;
+        reach = reach.max(end(line));
+        gap
+    });
+    match position {
+        Some(position) => {
+            let rest = lines.split_off(position);
+            Ok((lines, rest))
+        }
+        None => Err(lines),
+    }
+}
+
+///
+/// Orders lines with a recursive XY-cut: columns (vertical gaps no line crosses) are read left
+/// to right before rows (horizontal gaps) are read top to bottom, so that each column of a
+/// multi-column page is read through before the next one.
+fn reading_order(lines: Vec<Line>, depth: usize) -> Vec<Line> {
+    if lines.len() <= 1 || depth > 64 {
+        return lines;
+    }
+    let (first, rest) = match cut(lines, |line| line.left, |line| line.right) {
+        Ok(split) => split,
+        Err(lines) => match cut(lines, |line| line.top(), |line| line.y) {
+            Ok(split) => split,
+            Err(mut lines) => {
+                lines.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.left.total_cmp(&b.left)));
+                return lines;
+            }
+        },
+    };
+    let mut ordered = reading_order(first, depth + 1);
+    ordered.extend(reading_order(rest, depth + 1));
+    ordered
+}
+
+/// Assembles the page text in reading order, with a blank line between blocks.
+fn reading_order_text(glyphs: &[Glyph]) -> String {
+    let mut text = String::new();
+    let mut last: Option<Line> = None;
+    for line in reading_order(lines(glyphs), 0) {
+        if let Some(last) = &last {
+            // consecutive lines of the same block are close below each other and overlap
+            let dy = line.y - last.y;
+            let same_block = dy > 0.
+                && dy <= last.font_size.max(line.font_size) * 1.6
+                && line.left < last.right
+                && last.left < line.right;
+            text.push_str(if same_block { "\n" } else { "\n\n" });
+        }
+        text.push_str(&line.text());
+        last = Some(line);
+    }
+    text
+}
+
+///
+/// Assembles the page text with lines and horizontal positions as laid out on the page. Glyphs
+/// beyond the page `width` are kept at its right edge.
+fn physical_text(glyphs: &[Glyph], width: f64) -> String {
+    if glyphs.iter().all(|glyph| glyph.text.trim().is_empty()) {
+        return String::new();
+    }
+    let mut sorted: Vec<&Glyph> = glyphs.iter().collect();
+    sorted.sort_by(|a, b| a.y.total_cmp(&b.y));
+
+    // the median advance width is the width of one column of text
+    let mut widths: Vec<f64> = sorted
+        .iter()
+        .filter(|glyph| !glyph.text.trim().is_empty())
+        .map(|glyph| glyph.end - glyph.x)
+        .filter(|width| *width > 0.)
+        .collect();
+    widths.sort_by(f64::total_cmp);
+    let column_width = widths.get(widths.len() / 2).copied().unwrap_or(5.);
+    let left = sorted.iter().map(|glyph| glyph.x).fold(f64::MAX, f64::min);
+    let last_column = (width / column_width).clamp(0., MAX_COLUMNS) as usize;
+
+    let mut rows: Vec<Vec<&Glyph>> = Vec::new();
+    for glyph in sorted {
+        match rows.last_mut() {
+            Some(row) if glyph.y - row[0].y <= row[0].font_size.max(glyph.font_size) * 0.4 => {
+                row.push(glyph)
+            }
+            _ => rows.push(vec![glyph]),
+        }
+    }
+
+    let mut text = String::new();
+    let mut last_y: Option<(f64, f64)> = None;
+    for mut row in rows {
+        row.sort_by(|a, b| a.x.total_cmp(&b.x));
+        if let Some((y, font_size)) = last_y {
+            text.push('\n');
+            if row[0].y - y > font_size * 2. {
+                text.push('\n');
+            }
+        }
+        let mut line = String::new();
+        let mut last: Option<&Glyph> = None;
+        for glyph in &row {
+            let column = ((glyph.x - left) / column_width).round().max(0.) as usize;
+            let column = column.min(last_column);
+            let length = line.chars().count();
+            // only pad between words, so wide glyphs don't get spread out within a word
+            let apart = last.is_none_or(|last| glyph.x > last.end + glyph.font_size * 0.1);
+            if apart && column > length {
+                line.push_str(&" ".repeat(column - length));
+            } else if let Some(last) = last {
+                push_space(&mut line, last, glyph);
+            }
+            line.push_str(&glyph.text);
+            last = Some(glyph);
+        }
+        text.push_str(line.trim_end());
+        last_y = Some((row[0].y, row[0].font_size));
+    }
+    text
+}
+
+/// Assembles the text of a page with one of the layout-aware modes.
+pub(crate) fn layout_text(glyphs: &[Glyph], layout: PdfLayout, width: f64) -> String {
+    let text = match layout {
+        PdfLayout::Stream => return super::collector::flow_text(glyphs),
+        PdfLayout::ReadingOrder => reading_order_text(glyphs),
+        PdfLayout::Physical => physical_text(glyphs, width),
+    };
+    match text.is_empty() {
+        true => text,
+        false => text + "\n\n",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{document, save};
+    use crate::extraction::{PdfExtractor, PdfOptions};
+    use pretty_assertions::assert_eq;
+
+    const BITCOIN: &[u8] = include_bytes!("../../../tests/data/bitcoin.pdf");
+
+    /// Two columns drawn row by row, so that content stream order interleaves them.
+    fn two_columns() -> Vec<u8> {
+        let (mut document, _) = document(&["BT /F1 12 Tf \
+            1 0 0 1 72 720 Tm (Left one) Tj 1 0 0 1 320 720 Tm (Right one) Tj \
+            1 0 0 1 72 706 Tm (Left two) Tj 1 0 0 1 320 706 Tm (Right two) Tj ET"]);
+        save(&mut document)
+    }
+
+    fn page_text(data: &[u8], layout: PdfLayout) -> String {
+        let options = PdfOptions {
+            layout,
+            ..Default::default()
+        };
+        let document = PdfExtractor::extract_document(data, &options).unwrap();
+        document.pages[0].text.clone()
+    }
+
+    #[test]
+    fn test_reading_order() {
+        assert_eq!(
+            page_text(&two_columns(), PdfLayout::ReadingOrder),
+            "Left one\nLeft two\n\nRight one\nRight two\n\n"
+        );
+    }
+
+    #[test]
+    fn test_physical_layout() {
+        let text = page_text(&two_columns(), PdfLayout::Physical);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[0].starts_with("Left one  "));
+        assert!(lines[0].ends_with("  Right one"));
+        assert_eq!(lines[0].find("Right"), lines[1].find("Right"));
+    }
+
+    #[test]
+    fn test_physical_layout_off_page() {
+        let (mut document, _) = document(&["BT /F1 12 Tf \
+            1 0 0 1 72 720 Tm (Inside) Tj 1 0 0 1 20000000000 700 Tm (Outside) Tj ET"]);
+        let text = page_text(&save(&mut document), PdfLayout::Physical);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "Inside");
+        assert!(lines[1].trim_start().starts_with("Outside"));
+        assert!(lines[1].len() < 200);
+    }
+
+    #[test]
+    fn test_figures_stay_in_place() {
+        // content stream order puts the labels of the figure on page 2 after the text below it
+        let options = PdfOptions {
+            pages: Some(2..=2),
+            layout: PdfLayout::ReadingOrder,
+            ..Default::default()
+        };
+        let text = PdfExtractor::extract_document(BITCOIN, &options)
+            .unwrap()
+            .text();
+        let figure = text.find("Owner 0's").unwrap();
+
+        assert!(text.find("2. Transactions").unwrap() < figure);
+        assert!(figure < text.find("The problem of course").unwrap());
+    }
+}
@@ -0,0 +1,272 @@
+use lopdf::{Dictionary, Document, Object};
+use time::OffsetDateTime;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::extraction::dates::{parse_iso_date, parse_pdf_date};
+
+const DC: &str = "http://purl.org/dc/elements/1.1/";
+const XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const PDF: &str = "http://ns.adobe.com/pdf/1.3/";
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// Document information from a PDF's Info dictionary, completed with its XMP metadata.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    /// The application that created the original document.
+    pub creator: Option<String>,
+    /// The application that converted it to PDF.
+    pub producer: Option<String>,
+    pub creation_date: Option<OffsetDateTime>,
+    pub modification_date: Option<OffsetDateTime>,
+    /// The raw XMP packet of the document catalog, if any.
+    pub xmp: Option<String>,
+}
+
+/// PDFDocEncoding code points 0x18-0x1F and 0x80-0x9F that differ from Latin-1.
+const PDF_DOC_ENCODING: [(u8, char); 40] = [
+    (0x18, '\u{02D8}'),
+    (0x19, '\u{02C7}'),
+    (0x1A, '\u{02C6}'),
+    (0x1B, '\u{02D9}'),
+    (0x1C, '\u{02DD}'),
+    (0x1D, '\u{02DB}'),
+    (0x1E, '\u{02DA}'),
+    (0x1F, '\u{02DC}'),
+    (0x80, '\u{2022}'),
+    (0x81, '\u{2020}'),
+    (0x82, '\u{2021}'),
+    (0x83, '\u{2026}'),
+    (0x84, '\u{2014}'),
+    (0x85, '\u{2013}'),
+    (0x86, '\u{0192}'),
+    (0x87, '\u{2044}'),
+    (0x88, '\u{2039}'),
+    (0x89, '\u{203A}'),
+    (0x8A, '\u{2212}'),
+    (0x8B, '\u{2030}'),
+    (0x8C, '\u{201E}'),
+    (0x8D, '\u{201C}'),
+    (0x8E, '\u{201D}'),
+    (0x8F, '\u{2018}'),
+    (0x90, '\u{2019}'),
+    (0x91, '\u{201A}'),
+    (0x92, '\u{2122}'),
+    (0x93, '\u{FB01}'),
+    (0x94, '\u{FB02}'),
+    (0x95, '\u{0141}'),
+    (0x96, '\u{0152}'),
+    (0x97, '\u{0160}'),
+    (0x98, '\u{0178}'),
+    (0x99, '\u{017D}'),
+    (0x9A, '\u{0131}'),
+    (0x9B, '\u{0142}'),
+    (0x9C, '\u{0153}'),
+    (0x9D, '\u{0161}'),
+    (0x9E, '\u{017E}'),
+    (0xA0, '\u{20AC}'),
+];
+
+///
+/// Decodes a PDF text string: UTF-16BE or UTF-8 when it starts with a byte order mark,
+/// PDFDocEncoding otherwise.
+pub(crate) fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(utf8).into_owned();
+    }
+    bytes
+        .iter()
+        .map(|&byte| {
+            PDF_DOC_ENCODING
+                .iter()
+                .find(|(code, _)| *code == byte)
+                .map_or(char::from(byte), |(_, c)| *c)
+        })
+        .collect()
+}
+
+/// Metadata fields found in an XMP packet.
+#[derive(Debug, Default)]
+struct XmpFields {
+    title: Option<String>,
+    creators: Vec<String>,
+    description: Option<String>,
+    keywords: Option<String>,
+    creator_tool: Option<String>,
+    producer: Option<String>,
+    create_date: Option<String>,
+    modify_date: Option<String>,
+}
+
+impl XmpFields {
+    fn set(&mut self, namespace: &str, name: &str, value: String) {
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            return;
+        }
+        match (namespace, name) {
+            (DC, "title") => {
+                self.title.get_or_insert(value);
+            }
+            (DC, "creator") => self.creators.push(value),
+            (DC, "description") => {
+                self.description.get_or_insert(value);
+            }
+            (PDF, "Keywords") => self.keywords = Some(value),
+            (PDF, "Producer") => self.producer = Some(value),
+            (XMP, "CreatorTool") => self.creator_tool = Some(value),
+            (XMP, "CreateDate") => self.create_date = Some(value),
+            (XMP, "ModifyDate") => self.modify_date = Some(value),
+            _ => {}
+        }
+    }
+}
+
+///
+/// Collects the properties of every `rdf:Description`, whether they are written as attributes
+/// or as child elements. For array values (`rdf:Seq`, `rdf:Alt`...), each `rdf:li` is a value.
+fn parse_xmp(packet: &str) -> XmpFields {
+    let mut fields = XmpFields::default();
+    // (namespace, local name) of the property being read and the text collected so far
+    let mut property: Option<(String, String)> = None;
+    let mut text = String::new();
+    let mut depth_in_description = None;
+    let mut depth = 0;
+
+    for event in EventReader::new(packet.as_bytes()) {
+        match event {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => {
+                depth += 1;
+                let namespace = name.namespace.unwrap_or_default();
+                if namespace == RDF && name.local_name == "Description" {
+                    depth_in_description = Some(depth);
+                    for attribute in attributes {
+                        let namespace = attribute.name.namespace.unwrap_or_default();
+                        fields.set(&namespace, &attribute.name.local_name, attribute.value);
+                    }
+                } else if depth_in_description == Some(depth - 1) {
+                    property = Some((namespace, name.local_name));
+                    text.clear();
+                } else if namespace == RDF && name.local_name == "li" {
+                    text.clear();
+                }
+            }
+            Ok(XmlEvent::Characters(chars)) | Ok(XmlEvent::CData(chars)) => {
+                text.push_str(&chars);
+            }
+            Ok(XmlEvent::EndElement { name }) => {
+                let namespace = name.namespace.unwrap_or_default();
+                if let Some((property_namespace, property_name)) = &property {
+                    if namespace == RDF && name.local_name == "li" {
+                        fields.set(property_namespace, property_name, std::mem::take(&mut text));
+                    } else if depth_in_description == Some(depth - 1) {
+                        fields.set(property_namespace, property_name, std::mem::take(&mut text));
+                        property = None;
+                    }
+                }
+                if depth_in_description == Some(depth) {
+                    depth_in_description = None;
+                }
+                depth -= 1;
+            }
+            Err(_) => break,
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn info_text(info: &Dictionary, key: &[u8]) -> Option<String> {
+    let value = info.get(key).ok()?.as_str().ok()?;
+    let text = decode_text_string(value).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn xmp_packet(document: &Document) -> Option<String> {
+    let stream = document
+        .catalog()
+        .ok()?
+        .get(b"Metadata")
+        .and_then(Object::as_reference)
+        .and_then(|id| document.get_object(id))
+        .and_then(Object::as_stream)
+        .ok()?;
+    let content = match stream.dict.get(b"Filter") {
+        Ok(_) => stream.decompressed_content().ok()?,
+        Err(_) => stream.content.clone(),
+    };
+    Some(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Reads the metadata of a (decrypted) document. Info dictionary entries take precedence over
+/// XMP properties.
+pub(crate) fn read_metadata(document: &Document) -> PdfMetadata {
+    let empty = Dictionary::new();
+    let info = document
+        .trailer
+        .get(b"Info")
+        .and_then(|info| match info {
+            Object::Reference(id) => document.get_dictionary(*id),
+            other => other.as_dict(),
+        })
+        .unwrap_or(&empty);
+    let xmp = xmp_packet(document);
+    let fields = xmp.as_deref().map(parse_xmp).unwrap_or_default();
+    let creators = (!fields.creators.is_empty()).then(|| fields.creators.join(", "));
+
+    PdfMetadata {
+        title: info_text(info, b"Title").or(fields.title),
+        author: info_text(info, b"Author").or(creators),
+        subject: info_text(info, b"Subject").or(fields.description),
+        keywords: info_text(info, b"Keywords").or(fields.keywords),
+        creator: info_text(info, b"Creator").or(fields.creator_tool),
+        producer: info_text(info, b"Producer").or(fields.producer),
+        creation_date: info_text(info, b"CreationDate")
+            .and_then(|date| parse_pdf_date(&date))
+            .or_else(|| fields.create_date.as_deref().and_then(parse_iso_date)),
+        modification_date: info_text(info, b"ModDate")
+            .and_then(|date| parse_pdf_date(&date))
+            .or_else(|| fields.modify_date.as_deref().and_then(parse_iso_date)),
+        xmp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_xmp() {
+        let fields = parse_xmp(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description rdf:about="" xmlns:pdf="http://ns.adobe.com/pdf/1.3/" pdf:Keywords="rust, text"/>
+            <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/">
+              <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Quarterly report</rdf:li></rdf:Alt></dc:title>
+              <dc:creator><rdf:Seq><rdf:li>Ada</rdf:li><rdf:li>Grace</rdf:li></rdf:Seq></dc:creator>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#,
+        );
+
+        assert_eq!(fields.title.as_deref(), Some("Quarterly report"));
+        assert_eq!(fields.creators, ["Ada", "Grace"]);
+        assert_eq!(fields.keywords.as_deref(), Some("rust, text"));
+    }
+
+    #[test]
+    fn test_decode_text_string() {
+        assert_eq!(decode_text_string(b"\xfe\xff\x00W\x00r"), "Wr");
+        assert_eq!(decode_text_string(b"caf\xe9 \x93"), "café \u{FB01}");
+    }
+}
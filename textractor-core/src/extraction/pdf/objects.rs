@@ -0,0 +1,96 @@
+//! Helpers to read the objects of a PDF document, shared by the readers of its parts.
+
+use std::collections::HashSet;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::metadata::decode_text_string;
+
+/// Name trees are balanced, so real ones are shallow.
+const MAX_NAME_TREE_DEPTH: usize = 32;
+
+/// Follows a reference to the object it points to.
+pub(super) fn resolve<'a>(document: &'a Document, object: &'a Object) -> Option<&'a Object> {
+    match object {
+        Object::Reference(id) => document.get_object(*id).ok(),
+        other => Some(other),
+    }
+}
+
+/// A text string entry of a dictionary, trimmed, with line breaks normalized to `\n`.
+pub(super) fn text_entry(dict: &Dictionary, key: &[u8]) -> Option<String> {
+    let text = decode_text_string(dict.get(key).ok()?.as_str().ok()?);
+    let text = text.trim().replace("\r\n", "\n").replace('\r', "\n");
+    (!text.is_empty()).then_some(text)
+}
+
+/// The key/value pairs of a name tree (`/Names` pairs and `/Kids`), in key order.
+pub(super) fn name_tree_entries<'a>(
+    document: &'a Document,
+    node: &'a Dictionary,
+) -> Vec<(&'a [u8], &'a Object)> {
+    fn collect<'a>(
+        document: &'a Document,
+        node: &'a Dictionary,
+        depth: usize,
+        seen: &mut HashSet<ObjectId>,
+        entries: &mut Vec<(&'a [u8], &'a Object)>,
+    ) {
+        if depth > MAX_NAME_TREE_DEPTH {
+            return;
+        }
+        if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+            for pair in names.chunks_exact(2) {
+                if let (Ok(name), Some(value)) = (pair[0].as_str(), resolve(document, &pair[1])) {
+                    entries.push((name, value));
+                }
+            }
+        }
+        for kid in node
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            // a node listed twice, or among its own descendants, is only read once
+            if let Object::Reference(id) = kid {
+                if !seen.insert(*id) {
+                    continue;
+                }
+            }
+            if let Some(kid) = resolve(document, kid).and_then(|kid| kid.as_dict().ok()) {
+                collect(document, kid, depth + 1, seen, entries);
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    collect(document, node, 0, &mut HashSet::new(), &mut entries);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::string;
+    use lopdf::dictionary;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_name_tree_cycle() {
+        let mut document = Document::with_version("1.7");
+        let id = document.new_object_id();
+        document.objects.insert(
+            id,
+            Object::Dictionary(dictionary! {
+                "Names" => vec![string("a"), 1.into()],
+                "Kids" => vec![id.into(), id.into()],
+            }),
+        );
+        let root = dictionary! { "Kids" => vec![id.into()] };
+
+        let entries = name_tree_entries(&document, &root);
+
+        assert_eq!(entries, vec![(b"a".as_slice(), &Object::Integer(1))]);
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::{HashMap, HashSet};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::metadata::decode_text_string;
+use super::objects::{name_tree_entries, resolve};
+
+/// An entry of the document outline (bookmarks).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineItem {
+    pub title: String,
+    /// Nesting level, starting at 1 for top-level entries.
+    pub level: u32,
+    /// 1-based number of the destination page, if it could be resolved.
+    pub page: Option<u32>,
+    /// Vertical position of the destination on its page, in PDF user space, when known.
+    pub top: Option<f64>,
+    pub children: Vec<OutlineItem>,
+}
+
+// nesting limit, so broken or cyclic outlines can't loop forever
+const MAX_DEPTH: u32 = 64;
+
+/// Resolves a named destination through the catalog `/Dests` dictionary or `/Names` tree.
+fn named_destination<'a>(document: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = document.catalog().ok()?;
+    let destination = catalog
+        .get(b"Dests")
+        .ok()
+        .and_then(|dests| resolve(document, dests))
+        .and_then(|dests| dests.as_dict().ok())
+        .and_then(|dests| dests.get(name).ok())
+        .and_then(|dest| resolve(document, dest))
+        .or_else(|| {
+            let names = resolve(document, catalog.get(b"Names").ok()?)?
+                .as_dict()
+                .ok()?;
+            let tree = resolve(document, names.get(b"Dests").ok()?)?
+                .as_dict()
+                .ok()?;
+            name_tree_entries(document, tree)
+                .into_iter()
+                .find_map(|(key, value)| (key == name).then_some(value))
+        })?;
+    // named destinations may be wrapped in a dictionary with a /D entry
+    match destination {
+        Object::Dictionary(dict) => resolve(document, dict.get(b"D").ok()?),
+        other => Some(other),
+    }
+}
+
+/// Resolves an explicit or named destination to a page object and a top coordinate.
+fn destination(document: &Document, dest: &Object) -> Option<(ObjectId, Option<f64>)> {
+    let dest = match resolve(document, dest)? {
+        Object::Name(name) | Object::String(name, _) => named_destination(document, name)?,
+        other => other,
+    };
+    let array = dest.as_array().ok()?;
+    let page = array.first()?.as_reference().ok()?;
+    let coordinate = |index: usize| {
+        array
+            .get(index)
+            .and_then(|value| value.as_float().ok())
+            .map(f64::from)
+    };
+    let top = match array.get(1).and_then(|kind| kind.as_name().ok()) {
+        Some(b"XYZ") => coordinate(3),
+        Some(b"FitH") | Some(b"FitBH") => coordinate(2),
+        Some(b"FitR") => coordinate(5),
+        _ => None,
+    };
+    Some((page, top))
+}
+
+fn item_destination(document: &Document, item: &Dictionary) -> Option<(ObjectId, Option<f64>)> {
+    if let Ok(dest) = item.get(b"Dest") {
+        return destination(document, dest);
+    }
+    let action = resolve(document, item.get(b"A").ok()?)?.as_dict().ok()?;
+    if action.get(b"S").and_then(Object::as_name).ok() != Some(b"GoTo") {
+        return None;
+    }
+    destination(document, action.get(b"D").ok()?)
+}
+
+fn read_items(
+    document: &Document,
+    first: Option<ObjectId>,
+    level: u32,
+    page_numbers: &HashMap<ObjectId, u32>,
+    seen: &mut HashSet<ObjectId>,
+) -> Vec<OutlineItem> {
+    let mut items = Vec::new();
+    let mut next = first;
+    while let Some(id) = next {
+        if level > MAX_DEPTH || !seen.insert(id) {
+            break;
+        }
+        let Ok(item) = document.get_dictionary(id) else {
+            break;
+        };
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+
+        let title = item
+            .get(b"Title")
+            .and_then(Object::as_str)
+            .map(decode_text_string)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let (page, top) = match item_destination(document, item) {
+            Some((page, top)) => (page_numbers.get(&page).copied(), top),
+            None => (None, None),
+        };
+        let first_child = item.get(b"First").and_then(Object::as_reference).ok();
+
+        items.push(OutlineItem {
+            title,
+            level,
+            page,
+            top,
+            children: read_items(document, first_child, level + 1, page_numbers, seen),
+        });
+    }
+    items
+}
+
+/// Reads the document outline, resolving destinations to page numbers.
+pub(crate) fn read_outline(document: &Document) -> Vec<OutlineItem> {
+    let page_numbers: HashMap<ObjectId, u32> = document
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number))
+        .collect();
+    let first = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|outlines| resolve(document, outlines))
+        .and_then(|outlines| outlines.as_dict().ok())
+        .and_then(|outlines| outlines.get(b"First").and_then(Object::as_reference).ok());
+
+    read_items(document, first, 1, &page_numbers, &mut HashSet::new())
+}
+
+/// The outline items pointing to `page`, in outline order.
+pub(crate) fn page_headings(outline: &[OutlineItem], page: u32) -> Vec<&OutlineItem> {
+    let mut headings = Vec::new();
+    for item in outline {
+        if item.page == Some(page) && !item.title.is_empty() {
+            headings.push(item);
+        }
+        headings.extend(page_headings(&item.children, page));
+    }
+    headings
+}
+
+/// A Markdown-style heading for an outline item, followed by a blank line.
+pub(crate) fn heading_text(item: &OutlineItem) -> String {
+    format!("{} {}\n\n", "#".repeat(item.level as usize), item.title)
+}
+
+/// Heading blocks for [`flow_text_with_blocks`](super::collector::flow_text_with_blocks),
+/// positioned at their destination, or at the top of the page when it is unknown.
+pub(crate) fn heading_blocks(headings: &[&OutlineItem], height: f64) -> Vec<(Option<f64>, String)> {
+    headings
+        .iter()
+        .map(|item| (item.top.map(|top| height - top), heading_text(item)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{catalog, document, save, string};
+    use crate::extraction::{PdfExtractor, PdfOptions};
+    use lopdf::dictionary;
+    use pretty_assertions::assert_eq;
+
+    fn outlined_document() -> Vec<u8> {
+        let (mut document, pages) = document(&[
+            "BT /F1 12 Tf 72 720 Td (Intro text) Tj 0 -100 Td (Method text) Tj ET",
+            "BT /F1 12 Tf 72 720 Td (Results text) Tj ET",
+        ]);
+        let outlines = document.new_object_id();
+        let intro = document.new_object_id();
+        let method = document.new_object_id();
+        let results = document.new_object_id();
+        document.objects.insert(
+            intro,
+            Object::Dictionary(dictionary! {
+                "Title" => string("Introduction"),
+                "Parent" => outlines,
+                "Next" => results,
+                "First" => method,
+                "Last" => method,
+                "Dest" => vec![pages[0].into(), "XYZ".into(), 0.into(), 792.into(), Object::Null],
+            }),
+        );
+        document.objects.insert(
+            method,
+            Object::Dictionary(dictionary! {
+                "Title" => string("Method"),
+                "Parent" => intro,
+                "Dest" => string("method"),
+            }),
+        );
+        document.objects.insert(
+            results,
+            Object::Dictionary(dictionary! {
+                "Title" => string("Results"),
+                "Parent" => outlines,
+                "A" => dictionary! {
+                    "S" => "GoTo",
+                    "D" => vec![pages[1].into(), "Fit".into()],
+                },
+            }),
+        );
+        document.objects.insert(
+            outlines,
+            Object::Dictionary(dictionary! {
+                "Type" => "Outlines",
+                "First" => intro,
+                "Last" => results,
+            }),
+        );
+        let names = dictionary! {
+            "Dests" => dictionary! {
+                "Kids" => vec![Object::Dictionary(dictionary! {
+                    "Names" => vec![
+                        string("method"),
+                        vec![pages[0].into(), "FitH".into(), 640.into()].into(),
+                    ],
+                })],
+            },
+        };
+        let catalog = catalog(&mut document);
+        catalog.set("Outlines", outlines);
+        catalog.set("Names", names);
+        save(&mut document)
+    }
+
+    #[test]
+    fn test_read_outline() {
+        let document =
+            PdfExtractor::extract_document(&outlined_document(), &PdfOptions::default()).unwrap();
+        let outline = &document.outline;
+
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[0].title, "Introduction");
+        assert_eq!((outline[0].level, outline[0].page), (1, Some(1)));
+        assert_eq!(outline[0].children[0].title, "Method");
+        assert_eq!(outline[0].children[0].level, 2);
+        assert_eq!(outline[0].children[0].page, Some(1));
+        assert_eq!(outline[0].children[0].top, Some(640.));
+        assert_eq!(
+            (outline[1].title.as_str(), outline[1].page),
+            ("Results", Some(2))
+        );
+        assert!(!document.text().contains('#'));
+    }
+
+    #[test]
+    fn test_insert_headings() {
+        let options = PdfOptions {
+            insert_headings: true,
+            ..Default::default()
+        };
+        let document = PdfExtractor::extract_document(&outlined_document(), &options).unwrap();
+
+        assert_eq!(
+            document.pages[0].text.trim(),
+            "# Introduction\n\nIntro text\n\n## Method\n\nMethod text"
+        );
+        assert_eq!(document.pages[1].text.trim(), "# Results\n\nResults text");
+    }
+
+    #[test]
+    fn test_cyclic_outline() {
+        let (mut document, pages) = document(&["BT /F1 12 Tf 72 720 Td (Text) Tj ET"]);
+        let outlines = document.new_object_id();
+        let item = document.new_object_id();
+        document.objects.insert(
+            item,
+            Object::Dictionary(dictionary! {
+                "Title" => string("Loop"),
+                "Next" => item,
+                "First" => item,
+                "Dest" => vec![pages[0].into(), "Fit".into()],
+            }),
+        );
+        document.objects.insert(
+            outlines,
+            Object::Dictionary(dictionary! { "First" => item }),
+        );
+        catalog(&mut document).set("Outlines", outlines);
+
+        let document =
+            PdfExtractor::extract_document(&save(&mut document), &PdfOptions::default()).unwrap();
+
+        assert_eq!(document.outline.len(), 1);
+        assert!(document.outline[0].children.is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Recovery of PDFs with a missing or damaged cross-reference table.
+//!
+//! lopdf finds objects through the xref table only: when it can't be parsed the whole document
+//! fails to load, and when its offsets are wrong the objects are silently dropped. Like most
+//! viewers, we then rebuild the table by scanning the file for `N G obj` headers.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use lopdf::xref::{Xref, XrefEntry, XrefType};
+use lopdf::{Dictionary, Document, Object, ObjectId, ObjectStream, Reader};
+
+/// The trailer entries still meaningful once the xref table is rebuilt.
+const TRAILER_KEYS: [&[u8]; 4] = [b"Root", b"Info", b"ID", b"Encrypt"];
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn rfind(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+/// Parses a decimal number at the start of `data`, returning it with the number of digits.
+fn parse_number(data: &[u8]) -> Option<(u32, usize)> {
+    let digits = data.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&data[..digits]).ok()?.parse().ok()?;
+    Some((number, digits))
+}
+
+/// Parses an `N G obj` header at the start of `data`.
+fn object_header(data: &[u8]) -> Option<ObjectId> {
+    let (number, mut position) = parse_number(data)?;
+    let spaces = data[position..]
+        .iter()
+        .take_while(|b| is_whitespace(**b))
+        .count();
+    if spaces == 0 {
+        return None;
+    }
+    position += spaces;
+    let (generation, digits) = parse_number(&data[position..])?;
+    position += digits;
+    let spaces = data[position..]
+        .iter()
+        .take_while(|b| is_whitespace(**b))
+        .count();
+    if spaces == 0 || !data[position + spaces..].starts_with(b"obj") {
+        return None;
+    }
+    match data.get(position + spaces + 3) {
+        Some(byte) if !is_whitespace(*byte) && !is_delimiter(*byte) => None,
+        _ => Some((number, generation.try_into().ok()?)),
+    }
+}
+
+/// The offset of every object header in the file. Later definitions win, as with incremental
+/// updates.
+fn scan_objects(data: &[u8]) -> BTreeMap<u32, XrefEntry> {
+    let mut entries = BTreeMap::new();
+    for offset in 0..data.len() {
+        if offset > 0 && !is_whitespace(data[offset - 1]) {
+            continue;
+        }
+        if let Some((number, generation)) = object_header(&data[offset..]) {
+            let offset = offset as u32;
+            entries.insert(number, XrefEntry::Normal { offset, generation });
+        }
+    }
+    entries
+}
+
+/// Parses the dictionary following the last `trailer` keyword, by reading it as an object.
+fn trailer(data: &[u8]) -> Option<Dictionary> {
+    let start = rfind(data, b"trailer")? + b"trailer".len();
+    let mut buffer = b"0 0 obj\n".to_vec();
+    buffer.extend_from_slice(&data[start..]);
+    let mut document = Document::new();
+    document.reference_table.insert(
+        0,
+        XrefEntry::Normal {
+            offset: 0,
+            generation: 0,
+        },
+    );
+    let reader = Reader {
+        buffer: &buffer,
+        document,
+    };
+    reader.get_object((0, 0)).ok()?.as_dict().ok().cloned()
+}
+
+fn version(data: &[u8]) -> String {
+    let start = data
+        .windows(5)
+        .position(|w| w == b"%PDF-")
+        .map_or(0, |i| i + 5);
+    let version = data[start..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit() || **byte == b'.')
+        .count();
+    String::from_utf8_lossy(&data[start..start + version]).into_owned()
+}
+
+/// Loads a document from the objects found in the file, ignoring its xref table.
+fn rebuild(data: &[u8]) -> Option<Document> {
+    let entries = scan_objects(data);
+    let size = entries.keys().max()? + 1;
+    let mut reference_table = Xref::new(size, XrefType::CrossReferenceTable);
+    reference_table.entries = entries;
+
+    let mut document = Document::with_version(version(data));
+    document.max_id = size - 1;
+    document.reference_table = reference_table;
+    let reader = Reader {
+        buffer: data,
+        document,
+    };
+    let mut objects: BTreeMap<ObjectId, Object> = BTreeMap::new();
+    let mut compressed = Vec::new();
+    for (&number, entry) in &reader.document.reference_table.entries {
+        let XrefEntry::Normal { generation, .. } = *entry else {
+            continue;
+        };
+        let Ok(object) = reader.get_object((number, generation)) else {
+            continue;
+        };
+        if let Ok(stream) = object.as_stream() {
+            if stream.dict.type_is(b"ObjStm") {
+                if let Ok(objects) = ObjectStream::new(&mut stream.clone()) {
+                    compressed.extend(objects.objects);
+                }
+            }
+        }
+        objects.insert((number, generation), object);
+    }
+    let mut document = reader.document;
+    for (id, object) in compressed {
+        objects.entry(id).or_insert(object);
+    }
+
+    // the trailer of a damaged file may be gone too, xref streams carry a copy of it
+    let xref_stream = objects
+        .values()
+        .rev()
+        .filter_map(|object| object.as_stream().ok())
+        .find(|stream| stream.dict.type_is(b"XRef"))
+        .map(|stream| stream.dict.clone());
+    let source = trailer(data).or(xref_stream).unwrap_or_default();
+    for key in TRAILER_KEYS {
+        if let Ok(value) = source.get(key) {
+            document.trailer.set(key, value.clone());
+        }
+    }
+    let has_catalog = document
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .is_ok_and(|id| objects.contains_key(&id));
+    if !has_catalog {
+        let (id, _) = objects
+            .iter()
+            .rev()
+            .find(|(_, object)| object.type_name().ok() == Some("Catalog"))?;
+        document.trailer.set("Root", *id);
+    }
+    document.objects = objects;
+    Some(document)
+}
+
+/// Whether the document could be read well enough to find its pages.
+fn has_pages(document: &Document) -> bool {
+    !document.get_pages().is_empty()
+}
+
+///
+/// Loads a PDF, falling back to rebuilding the xref table from the objects in the file when it
+/// can't be read or leads to a document without pages.
+pub(crate) fn load(data: &[u8]) -> Result<Document> {
+    let loaded = Document::load_mem(data);
+    if loaded.as_ref().is_ok_and(has_pages) {
+        return Ok(loaded?);
+    }
+    match (loaded, rebuild(data)) {
+        (_, Some(rebuilt)) if has_pages(&rebuilt) => Ok(rebuilt),
+        (loaded, _) => loaded.context("Failed to read PDF"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{document, save};
+    use crate::extraction::{Extract, PdfExtractor};
+
+    fn saved() -> Vec<u8> {
+        let (mut document, _) = document(&[
+            "BT /F1 12 Tf 72 720 Td (First page) Tj ET",
+            "BT /F1 12 Tf 72 720 Td (Second page) Tj ET",
+        ]);
+        save(&mut document)
+    }
+
+    #[test]
+    fn test_missing_xref() {
+        let data = saved();
+        let xref = rfind(&data, b"xref").unwrap();
+        // truncated right after the objects, as with an interrupted download
+        let text = PdfExtractor::extract(&data[..xref]).unwrap();
+
+        assert!(text.contains("First page"));
+        assert!(text.contains("Second page"));
+    }
+
+    #[test]
+    fn test_shifted_offsets() {
+        // bytes inserted in front of the objects invalidate every offset of the xref table
+        let data = saved();
+        let header = data.iter().position(|byte| *byte == b'\n').unwrap() + 1;
+        let mut shifted = data[..header].to_vec();
+        shifted.extend_from_slice(b"% some junk added by a broken mail gateway\n");
+        shifted.extend_from_slice(&data[header..]);
+
+        let text = PdfExtractor::extract(&shifted).unwrap();
+
+        assert!(text.contains("First page"));
+        assert!(text.contains("Second page"));
+    }
+
+    #[test]
+    fn test_object_header() {
+        assert_eq!(object_header(b"12 0 obj\n<<>>"), Some((12, 0)));
+        assert_eq!(object_header(b"3 1 obj<<>>"), Some((3, 1)));
+        assert_eq!(object_header(b"12 0 R"), None);
+        assert_eq!(object_header(b"12 0 objects"), None);
+    }
+}
@@ -0,0 +1,559 @@
+//! The PDF standard security handler (RC4, AES-128 and AES-256 encryption).
+//!
+//! lopdf only understands RC4, and can't expand object streams while the document is still
+//! encrypted, so decryption is done here directly on the loaded objects.
+
+use std::collections::BTreeSet;
+
+use aes::cipher::consts::U16;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes256};
+use anyhow::{bail, Context, Result};
+use lopdf::xref::XrefEntry;
+use lopdf::{Dictionary, Document, Object, ObjectId, ObjectStream, Reader};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::errors::ExtractionError;
+
+const PAD_BYTES: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08,
+    0x2E, 0x2E, 0x00, 0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CryptMethod {
+    Identity,
+    Rc4,
+    Aes128,
+    Aes256,
+}
+
+/// The parsed `/Encrypt` dictionary of a document.
+struct SecurityHandler {
+    revision: i64,
+    /// Length of the file key in bytes.
+    key_length: usize,
+    owner: Vec<u8>,
+    user: Vec<u8>,
+    owner_key: Vec<u8>,
+    user_key: Vec<u8>,
+    permissions: i32,
+    encrypt_metadata: bool,
+    file_id: Vec<u8>,
+    streams: CryptMethod,
+    strings: CryptMethod,
+}
+
+fn bytes(dict: &Dictionary, key: &[u8]) -> Vec<u8> {
+    dict.get(key)
+        .and_then(Object::as_str)
+        .map(<[u8]>::to_vec)
+        .unwrap_or_default()
+}
+
+fn crypt_filter(dict: &Dictionary, key: &[u8]) -> Result<CryptMethod> {
+    let name = match dict.get(key).and_then(Object::as_name) {
+        Ok(name) => name,
+        Err(_) => return Ok(CryptMethod::Identity),
+    };
+    if name == b"Identity" {
+        return Ok(CryptMethod::Identity);
+    }
+    let method = dict
+        .get(b"CF")
+        .and_then(Object::as_dict)
+        .and_then(|filters| filters.get(name))
+        .and_then(Object::as_dict)
+        .and_then(|filter| filter.get(b"CFM"))
+        .and_then(Object::as_name);
+    match method {
+        Ok(b"V2") => Ok(CryptMethod::Rc4),
+        Ok(b"AESV2") => Ok(CryptMethod::Aes128),
+        Ok(b"AESV3") => Ok(CryptMethod::Aes256),
+        Ok(b"None") | Err(_) => Ok(CryptMethod::Identity),
+        Ok(other) => bail!(
+            "Unsupported PDF crypt filter method {}",
+            String::from_utf8_lossy(other)
+        ),
+    }
+}
+
+impl SecurityHandler {
+    fn new(document: &Document, dict: &Dictionary) -> Result<SecurityHandler> {
+        let filter = dict.get(b"Filter").and_then(Object::as_name).unwrap_or(b"");
+        if filter != b"Standard" {
+            bail!(
+                "Unsupported PDF security handler {}",
+                String::from_utf8_lossy(filter)
+            );
+        }
+
+        let version = dict.get(b"V").and_then(Object::as_i64).unwrap_or(0);
+        let revision = dict
+            .get(b"R")
+            .and_then(Object::as_i64)
+            .context("PDF encryption dictionary has no revision")?;
+        // 256-bit keys only go with the SHA-2 based revisions, the MD5 ones can't derive them
+        if version == 5 && revision < 5 {
+            return Err(ExtractionError::CorruptDocument(format!(
+                "PDF encryption version 5 with revision {}",
+                revision
+            ))
+            .into());
+        }
+        let (streams, strings) = match version {
+            1 | 2 => (CryptMethod::Rc4, CryptMethod::Rc4),
+            4 | 5 => (crypt_filter(dict, b"StmF")?, crypt_filter(dict, b"StrF")?),
+            _ => bail!("Unsupported PDF encryption version {}", version),
+        };
+        let key_length = match version {
+            1 => 5,
+            5 => 32,
+            // /Length is in bits, but some writers put the byte count in crypt filters
+            _ => match dict.get(b"Length").and_then(Object::as_i64).unwrap_or(128) {
+                bits @ 40..=128 => bits as usize / 8,
+                bytes @ 5..=16 => bytes as usize,
+                _ => 16,
+            },
+        };
+        let file_id = document
+            .trailer
+            .get(b"ID")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|ids| ids.first())
+            .and_then(|id| id.as_str().ok())
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default();
+
+        Ok(SecurityHandler {
+            revision,
+            key_length,
+            owner: bytes(dict, b"O"),
+            user: bytes(dict, b"U"),
+            owner_key: bytes(dict, b"OE"),
+            user_key: bytes(dict, b"UE"),
+            permissions: dict.get(b"P").and_then(Object::as_i64).unwrap_or(-1) as i32,
+            encrypt_metadata: dict
+                .get(b"EncryptMetadata")
+                .and_then(Object::as_bool)
+                .unwrap_or(true),
+            file_id,
+            streams,
+            strings,
+        })
+    }
+
+    /// Returns the file key if `password` is either the user or the owner password.
+    fn authenticate(&self, password: &[u8]) -> Option<Vec<u8>> {
+        if self.revision >= 5 {
+            self.authenticate_aes256(password)
+        } else {
+            self.authenticate_user(password)
+                .or_else(|| self.authenticate_owner(password))
+        }
+    }
+
+    /// Algorithm 2: computes the file key from a user password (revisions 2 to 4).
+    fn file_key(&self, password: &[u8]) -> Vec<u8> {
+        let mut input = padded(password).to_vec();
+        input.extend_from_slice(&self.owner);
+        input.extend_from_slice(&self.permissions.to_le_bytes());
+        input.extend_from_slice(&self.file_id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            input.extend_from_slice(&[0xFF; 4]);
+        }
+
+        let mut key = md5::compute(&input).to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                key = md5::compute(&key[..self.key_length]).to_vec();
+            }
+        }
+        key.truncate(self.key_length);
+        key
+    }
+
+    /// Algorithms 4 and 5: checks a user password against `/U`.
+    fn authenticate_user(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let key = self.file_key(password);
+        let valid = if self.revision == 2 {
+            rc4(&key, &PAD_BYTES) == self.user
+        } else {
+            let mut input = PAD_BYTES.to_vec();
+            input.extend_from_slice(&self.file_id);
+            let mut hash = rc4(&key, &md5::compute(&input).0);
+            for i in 1..=19 {
+                hash = rc4(&xor_key(&key, i), &hash);
+            }
+            self.user.get(..16) == Some(&hash[..16])
+        };
+        valid.then_some(key)
+    }
+
+    /// Algorithm 7: recovers the user password from `/O` with the owner password.
+    fn authenticate_owner(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let mut key = md5::compute(padded(password)).to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                key = md5::compute(&key).to_vec();
+            }
+        }
+        key.truncate(self.key_length);
+
+        let user_password = if self.revision == 2 {
+            rc4(&key, &self.owner)
+        } else {
+            (0..=19)
+                .rev()
+                .fold(self.owner.clone(), |data, i| rc4(&xor_key(&key, i), &data))
+        };
+        self.authenticate_user(&user_password)
+    }
+
+    /// Algorithms 2.A, 11 and 12: AES-256 (revisions 5 and 6).
+    fn authenticate_aes256(&self, password: &[u8]) -> Option<Vec<u8>> {
+        if self.owner.len() < 48 || self.user.len() < 48 {
+            return None;
+        }
+        let password = &password[..password.len().min(127)];
+        let user = &self.user[..48];
+
+        let (hash, key) = if self.hash(password, &self.owner[32..40], user)? == self.owner[..32] {
+            (
+                self.hash(password, &self.owner[40..48], user)?,
+                &self.owner_key,
+            )
+        } else if self.hash(password, &self.user[32..40], &[])? == self.user[..32] {
+            (
+                self.hash(password, &self.user[40..48], &[])?,
+                &self.user_key,
+            )
+        } else {
+            return None;
+        };
+
+        if key.len() < 32 {
+            return None;
+        }
+        let cipher = Aes256::new_from_slice(&hash).ok()?;
+        Some(cbc_decrypt_blocks(&cipher, &[0; 16], &key[..32]))
+    }
+
+    /// The password hash of revision 5 (plain SHA-256) and revision 6 (algorithm 2.B).
+    fn hash(&self, password: &[u8], salt: &[u8], user: &[u8]) -> Option<Vec<u8>> {
+        let mut k = Sha256::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .chain_update(user)
+            .finalize()
+            .to_vec();
+        if self.revision == 5 {
+            return Some(k);
+        }
+
+        let mut round = 0;
+        let mut e: Vec<u8> = Vec::new();
+        while round < 64 || usize::from(*e.last().unwrap_or(&0)) + 32 > round {
+            let mut k1 = Vec::with_capacity(64 * (password.len() + k.len() + user.len()));
+            for _ in 0..64 {
+                k1.extend_from_slice(password);
+                k1.extend_from_slice(&k);
+                k1.extend_from_slice(user);
+            }
+            let cipher = Aes128::new_from_slice(&k[..16]).ok()?;
+            e = cbc_encrypt_blocks(&cipher, &k[16..32], &k1);
+            k = match e[..16].iter().map(|&b| u32::from(b)).sum::<u32>() % 3 {
+                0 => Sha256::digest(&e).to_vec(),
+                1 => Sha384::digest(&e).to_vec(),
+                _ => Sha512::digest(&e).to_vec(),
+            };
+            round += 1;
+        }
+        k.truncate(32);
+        Some(k)
+    }
+
+    /// Decrypts a string or stream of the object `id` with the file `key`.
+    fn decrypt(
+        &self,
+        key: &[u8],
+        id: ObjectId,
+        method: CryptMethod,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let decrypted = match method {
+            CryptMethod::Identity => Some(data.to_vec()),
+            CryptMethod::Aes256 => aes_decrypt(key, data),
+            CryptMethod::Rc4 | CryptMethod::Aes128 => {
+                // Algorithm 1: derive a key for this object
+                let mut input = key.to_vec();
+                input.extend_from_slice(&id.0.to_le_bytes()[..3]);
+                input.extend_from_slice(&id.1.to_le_bytes());
+                if method == CryptMethod::Aes128 {
+                    input.extend_from_slice(b"sAlT");
+                }
+                let digest = md5::compute(&input);
+                let object_key = &digest[..(key.len() + 5).min(16)];
+                if method == CryptMethod::Rc4 {
+                    Some(rc4(object_key, data))
+                } else {
+                    aes_decrypt(object_key, data)
+                }
+            }
+        };
+        decrypted.ok_or_else(|| {
+            ExtractionError::CorruptDocument(format!(
+                "invalid {}-byte key for {:?} encryption",
+                key.len(),
+                method
+            ))
+            .into()
+        })
+    }
+
+    fn decrypt_strings(&self, key: &[u8], id: ObjectId, object: &mut Object) -> Result<()> {
+        match object {
+            Object::String(content, _) => {
+                *content = self.decrypt(key, id, self.strings, content)?;
+            }
+            Object::Array(items) => {
+                for item in items {
+                    self.decrypt_strings(key, id, item)?;
+                }
+            }
+            Object::Dictionary(dict) => {
+                for (_, value) in dict.iter_mut() {
+                    self.decrypt_strings(key, id, value)?;
+                }
+            }
+            Object::Stream(stream) => {
+                for (_, value) in stream.dict.iter_mut() {
+                    self.decrypt_strings(key, id, value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn decrypt_object(&self, key: &[u8], id: ObjectId, object: &mut Object) -> Result<()> {
+        if let Object::Stream(stream) = object {
+            if stream.dict.type_is(b"XRef")
+                || (stream.dict.type_is(b"Metadata") && !self.encrypt_metadata)
+            {
+                return Ok(());
+            }
+            let content = self.decrypt(key, id, self.streams, &stream.content)?;
+            stream.set_content(content);
+        }
+        self.decrypt_strings(key, id, object)
+    }
+}
+
+fn padded(password: &[u8]) -> [u8; 32] {
+    let mut padded = PAD_BYTES;
+    let length = password.len().min(32);
+    padded[..length].copy_from_slice(&password[..length]);
+    padded[length..].copy_from_slice(&PAD_BYTES[..32 - length]);
+    padded
+}
+
+fn xor_key(key: &[u8], value: u8) -> Vec<u8> {
+    key.iter().map(|b| b ^ value).collect()
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            let index = state[i as usize].wrapping_add(state[j as usize]);
+            byte ^ state[index as usize]
+        })
+        .collect()
+}
+
+fn cbc_encrypt_blocks<C: BlockEncrypt<BlockSize = U16>>(
+    cipher: &C,
+    iv: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let mut previous: [u8; 16] = iv.try_into().unwrap();
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks_exact(16) {
+        let mut block = [0u8; 16];
+        for (out, (a, b)) in block.iter_mut().zip(chunk.iter().zip(previous.iter())) {
+            *out = a ^ b;
+        }
+        cipher.encrypt_block((&mut block).into());
+        output.extend_from_slice(&block);
+        previous = block;
+    }
+    output
+}
+
+fn cbc_decrypt_blocks<C: BlockDecrypt<BlockSize = U16>>(
+    cipher: &C,
+    iv: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let mut previous: [u8; 16] = iv.try_into().unwrap();
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks_exact(16) {
+        let mut block: [u8; 16] = chunk.try_into().unwrap();
+        cipher.decrypt_block((&mut block).into());
+        output.extend(block.iter().zip(previous.iter()).map(|(a, b)| a ^ b));
+        previous = chunk.try_into().unwrap();
+    }
+    output
+}
+
+///
+/// Decrypts AES-CBC data prefixed with its IV and removes the PKCS#7 padding. Fails when the key
+/// is not an AES-128 or AES-256 key.
+fn aes_decrypt(key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    let mut output = match key.len() {
+        16 | 32 if data.len() < 32 => return Some(Vec::new()),
+        16 => cbc_decrypt_blocks(&Aes128::new_from_slice(key).ok()?, &data[..16], &data[16..]),
+        32 => cbc_decrypt_blocks(&Aes256::new_from_slice(key).ok()?, &data[..16], &data[16..]),
+        _ => return None,
+    };
+    if let Some(&padding) = output.last() {
+        if (1..=16).contains(&padding) && usize::from(padding) <= output.len() {
+            output.truncate(output.len() - usize::from(padding));
+        }
+    }
+    Some(output)
+}
+
+///
+/// Decrypts every string and stream of an encrypted document in place, trying `password` as
+/// both the user and the owner password. Documents that open with an empty user password don't
+/// need one.
+pub(crate) fn decrypt(document: &mut Document, data: &[u8], password: Option<&str>) -> Result<()> {
+    let Ok(encrypt) = document.trailer.get(b"Encrypt") else {
+        return Ok(());
+    };
+    let (encrypt_id, dict) = match encrypt {
+        Object::Reference(id) => (Some(*id), document.get_dictionary(*id)?.clone()),
+        Object::Dictionary(dict) => (None, dict.clone()),
+        _ => bail!("Invalid PDF encryption dictionary"),
+    };
+    let handler = SecurityHandler::new(document, &dict)?;
+
+    let key = password
+        .and_then(|password| handler.authenticate(password.as_bytes()))
+        .or_else(|| handler.authenticate(b""));
+    let Some(key) = key else {
+        return Err(match password {
+            Some(_) => ExtractionError::IncorrectPassword.into(),
+            None => ExtractionError::PasswordRequired.into(),
+        });
+    };
+    for (&id, object) in document.objects.iter_mut() {
+        if Some(id) != encrypt_id {
+            handler.decrypt_object(&key, id, object)?;
+        }
+    }
+
+    // object streams can only be parsed once decrypted, so lopdf skipped them on load
+    let containers: BTreeSet<u32> = document
+        .reference_table
+        .entries
+        .values()
+        .filter_map(|entry| match entry {
+            XrefEntry::Compressed { container, .. } => Some(*container),
+            _ => None,
+        })
+        .collect();
+    let mut reader = Reader {
+        buffer: data,
+        document: std::mem::replace(document, Document::new()),
+    };
+    for container in containers {
+        let id = (container, 0);
+        let stream = match reader.document.objects.get(&id) {
+            Some(object) => object.clone(),
+            None => match reader.get_object(id) {
+                Ok(mut object) => {
+                    handler.decrypt_object(&key, id, &mut object)?;
+                    object
+                }
+                Err(_) => continue,
+            },
+        };
+        if let Ok(mut stream) = stream.as_stream().cloned() {
+            if let Ok(objects) = ObjectStream::new(&mut stream) {
+                for (id, object) in objects.objects {
+                    reader.document.objects.entry(id).or_insert(object);
+                }
+            }
+        }
+    }
+    *document = reader.document;
+
+    document.trailer.remove(b"Encrypt");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn test_aes_with_short_key_is_corrupt_document() {
+        // a 40-bit file key with an AESV2 crypt filter gives a 10-byte object key
+        let handler = SecurityHandler {
+            revision: 4,
+            key_length: 5,
+            owner: Vec::new(),
+            user: Vec::new(),
+            owner_key: Vec::new(),
+            user_key: Vec::new(),
+            permissions: -4,
+            encrypt_metadata: true,
+            file_id: Vec::new(),
+            streams: CryptMethod::Aes128,
+            strings: CryptMethod::Aes128,
+        };
+
+        let error = handler
+            .decrypt(&[0; 5], (1, 0), CryptMethod::Aes128, &[0; 32])
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::CorruptDocument(_))
+        ));
+        assert_eq!(aes_decrypt(&[0; 16], &[0; 8]), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_version_5_with_md5_revision_is_corrupt_document() {
+        let dict = dictionary! {
+            "Filter" => "Standard",
+            "V" => 5,
+            "R" => 3,
+        };
+
+        let error = SecurityHandler::new(&Document::with_version("1.7"), &dict)
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::CorruptDocument(_))
+        ));
+    }
+}
@@ -0,0 +1,469 @@
+use super::collector::{Glyph, Ruling};
+use super::layout::{lines, Line};
+
+/// A table detected on a PDF page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfTable {
+    /// The cells of each row, top to bottom. All rows have the same number of cells.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl PdfTable {
+    /// The table as CSV, quoting cells that contain commas, quotes or line breaks.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| match cell.contains([',', '"', '\n', '\r']) {
+                    true => format!("\"{}\"", cell.replace('"', "\"\"")),
+                    false => cell.clone(),
+                })
+                .collect();
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// The table as a Markdown (GFM) table, using the first row as the header.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| cell.replace('|', "\\|").replace('\n', " "))
+                .collect();
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if i == 0 {
+                markdown.push_str(&format!("|{}\n", " --- |".repeat(row.len())));
+            }
+        }
+        markdown
+    }
+}
+
+/// A detected table with the area it covers, in page space.
+pub(crate) struct TableRegion {
+    pub table: PdfTable,
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl TableRegion {
+    /// Whether the center of the glyph lies in the table.
+    pub fn contains(&self, glyph: &Glyph) -> bool {
+        let (x, y) = center(glyph);
+        (self.left..=self.right).contains(&x) && (self.top..=self.bottom).contains(&y)
+    }
+}
+
+// distance under which rulings are considered to touch or be aligned
+const TOLERANCE: f64 = 2.;
+// tables found from text alignment alone need at least this many rows
+const MIN_ALIGNED_ROWS: usize = 3;
+
+fn center(glyph: &Glyph) -> (f64, f64) {
+    ((glyph.x + glyph.end) / 2., glyph.y - glyph.font_size / 3.)
+}
+
+/// The text of the glyphs of a cell, with its lines joined by spaces.
+fn cell_text(glyphs: &[Glyph]) -> String {
+    let mut lines = lines(glyphs);
+    lines.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.left.total_cmp(&b.left)));
+    lines
+        .iter()
+        .map(Line::text)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Joins collinear rulings that overlap or touch.
+fn merge(mut rulings: Vec<Ruling>) -> Vec<Ruling> {
+    rulings.sort_by(|a, b| {
+        a.position
+            .total_cmp(&b.position)
+            .then(a.start.total_cmp(&b.start))
+    });
+    let mut merged: Vec<Ruling> = Vec::new();
+    for ruling in rulings {
+        match merged.last_mut() {
+            Some(last)
+                if (ruling.position - last.position).abs() <= TOLERANCE
+                    && ruling.start <= last.end + TOLERANCE =>
+            {
+                last.end = last.end.max(ruling.end);
+            }
+            _ => merged.push(ruling),
+        }
+    }
+    merged
+}
+
+fn intersects(horizontal: &Ruling, vertical: &Ruling) -> bool {
+    vertical.position >= horizontal.start - TOLERANCE
+        && vertical.position <= horizontal.end + TOLERANCE
+        && horizontal.position >= vertical.start - TOLERANCE
+        && horizontal.position <= vertical.end + TOLERANCE
+}
+
+/// Sorted positions, with the ones closer than the tolerance merged.
+fn cluster(mut positions: Vec<f64>) -> Vec<f64> {
+    positions.sort_by(f64::total_cmp);
+    let mut clustered: Vec<f64> = Vec::new();
+    for position in positions {
+        match clustered.last() {
+            Some(last) if position - last <= TOLERANCE => {}
+            _ => clustered.push(position),
+        }
+    }
+    clustered
+}
+
+/// Removes the rows and columns without any text.
+fn tidy(mut rows: Vec<Vec<String>>) -> Option<PdfTable> {
+    rows.retain(|row| row.iter().any(|cell| !cell.is_empty()));
+    let columns = rows.first()?.len();
+    let kept: Vec<usize> = (0..columns)
+        .filter(|column| rows.iter().any(|row| !row[*column].is_empty()))
+        .collect();
+    if rows.len() < 2 || kept.len() < 2 {
+        return None;
+    }
+    let rows = rows
+        .into_iter()
+        .map(|row| kept.iter().map(|column| row[*column].clone()).collect())
+        .collect();
+    Some(PdfTable { rows })
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[i] = root;
+    root
+}
+
+///
+/// Tables drawn with ruling lines: connected horizontal and vertical rulings form a grid, whose
+/// cells get the glyphs centered in them. Cells spanning several columns (where a vertical
+/// ruling doesn't cross the row) are kept in their first column.
+fn ruled_tables(glyphs: &[Glyph], rulings: &[Ruling]) -> Vec<TableRegion> {
+    let horizontals = merge(rulings.iter().filter(|r| r.horizontal).copied().collect());
+    let verticals = merge(rulings.iter().filter(|r| !r.horizontal).copied().collect());
+    let count = horizontals.len();
+
+    let mut parent: Vec<usize> = (0..count + verticals.len()).collect();
+    for (i, horizontal) in horizontals.iter().enumerate() {
+        for (j, vertical) in verticals.iter().enumerate() {
+            if intersects(horizontal, vertical) {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, count + j));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut regions = Vec::new();
+    let mut roots: Vec<usize> = (0..parent.len()).map(|i| find(&mut parent, i)).collect();
+    let components = roots.clone();
+    roots.sort_unstable();
+    roots.dedup();
+    for root in roots {
+        let in_component = |i: usize| components[i] == root;
+        let rows: Vec<&Ruling> = (0..count)
+            .filter(|i| in_component(*i))
+            .map(|i| &horizontals[i])
+            .collect();
+        let columns: Vec<&Ruling> = (0..verticals.len())
+            .filter(|j| in_component(count + j))
+            .map(|j| &verticals[j])
+            .collect();
+        let ys = cluster(rows.iter().map(|r| r.position).collect());
+        let xs = cluster(columns.iter().map(|r| r.position).collect());
+        if ys.len() < 2 || xs.len() < 2 {
+            continue;
+        }
+
+        let mut cells = Vec::new();
+        for band in ys.windows(2) {
+            let (top, bottom) = (band[0], band[1]);
+            let middle = (top + bottom) / 2.;
+            // the boundaries actually drawn across this row; the outer ones always count
+            let boundaries: Vec<usize> = (0..xs.len())
+                .filter(|i| {
+                    *i == 0
+                        || *i == xs.len() - 1
+                        || columns.iter().any(|v| {
+                            (v.position - xs[*i]).abs() <= TOLERANCE
+                                && v.start - TOLERANCE <= middle
+                                && v.end + TOLERANCE >= middle
+                        })
+                })
+                .collect();
+            let mut row = vec![String::new(); xs.len() - 1];
+            for pair in boundaries.windows(2) {
+                let (left, right) = (xs[pair[0]], xs[pair[1]]);
+                let cell: Vec<Glyph> = glyphs
+                    .iter()
+                    .filter(|glyph| {
+                        let (x, y) = center(glyph);
+                        (left..right).contains(&x) && (top..bottom).contains(&y)
+                    })
+                    .cloned()
+                    .collect();
+                row[pair[0]] = cell_text(&cell);
+            }
+            cells.push(row);
+        }
+
+        if let Some(table) = tidy(cells) {
+            regions.push(TableRegion {
+                table,
+                left: xs[0],
+                top: ys[0],
+                right: xs[xs.len() - 1],
+                bottom: ys[ys.len() - 1],
+            });
+        }
+    }
+    regions
+}
+
+///
+/// A table from rows of text fragments: columns are where the fragments of the rows overlap
+/// horizontally. Fragments as wide as a column of prose mean these are text columns instead, and
+/// mostly empty cells mean scattered labels.
+fn aligned_table(rows: &[Vec<Line>]) -> Option<TableRegion> {
+    let mut spans: Vec<(f64, f64)> = rows
+        .iter()
+        .flatten()
+        .map(|line| (line.left, line.right))
+        .collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (left, right) in spans.iter().copied() {
+        match columns.last_mut() {
+            Some(column) if left <= column.1 => column.1 = column.1.max(right),
+            _ => columns.push((left, right)),
+        }
+    }
+    let (left, right) = (columns.first()?.0, columns.last()?.1);
+    let mean_width = spans.iter().map(|(l, r)| r - l).sum::<f64>() / spans.len() as f64;
+    if columns.len() < 2 || mean_width > (right - left) * 0.35 {
+        return None;
+    }
+
+    let cells = rows
+        .iter()
+        .map(|row| {
+            let mut cells = vec![String::new(); columns.len()];
+            for line in row {
+                let Some(column) = columns.iter().position(|c| line.left <= c.1) else {
+                    continue;
+                };
+                if !cells[column].is_empty() {
+                    cells[column].push(' ');
+                }
+                cells[column].push_str(&line.text());
+            }
+            cells
+        })
+        .collect();
+
+    // sparse grids come from scattered labels (diagrams, rotated text) rather than tables
+    let table = tidy(cells)?;
+    let filled = table
+        .rows
+        .iter()
+        .flatten()
+        .filter(|cell| !cell.is_empty())
+        .count();
+    if filled * 2 < table.rows.len() * table.rows[0].len() {
+        return None;
+    }
+
+    let first = &rows[0][0];
+    let last = &rows[rows.len() - 1][0];
+    Some(TableRegion {
+        table,
+        left,
+        top: first.y - first.font_size,
+        right,
+        bottom: last.y + last.font_size / 3.,
+    })
+}
+
+/// Tables without rulings: at least a few consecutive rows made of several aligned fragments.
+fn aligned_tables(glyphs: &[Glyph]) -> Vec<TableRegion> {
+    let mut fragments: Vec<Line> = lines(glyphs)
+        .into_iter()
+        .filter(|line| !line.text().is_empty())
+        .collect();
+    fragments.sort_by(|a, b| a.y.total_cmp(&b.y));
+
+    let mut rows: Vec<Vec<Line>> = Vec::new();
+    for fragment in fragments {
+        match rows.last_mut() {
+            Some(row)
+                if (fragment.y - row[0].y).abs()
+                    <= row[0].font_size.max(fragment.font_size) * 0.4 =>
+            {
+                row.push(fragment)
+            }
+            _ => rows.push(vec![fragment]),
+        }
+    }
+    for row in &mut rows {
+        row.sort_by(|a, b| a.left.total_cmp(&b.left));
+    }
+
+    let mut regions = Vec::new();
+    let mut start = 0;
+    while start < rows.len() {
+        let mut end = start;
+        while end < rows.len()
+            && rows[end].len() >= 2
+            && (end == start || rows[end][0].y - rows[end - 1][0].y <= rows[end][0].font_size * 2.5)
+        {
+            end += 1;
+        }
+        if end - start >= MIN_ALIGNED_ROWS {
+            regions.extend(aligned_table(&rows[start..end]));
+        }
+        start = end.max(start + 1);
+    }
+    regions
+}
+
+/// Detects the tables of a page, from its rulings first, then from the alignment of the rest.
+pub(crate) fn detect_tables(glyphs: &[Glyph], rulings: &[Ruling]) -> Vec<TableRegion> {
+    let mut regions = ruled_tables(glyphs, rulings);
+    let rest: Vec<Glyph> = glyphs
+        .iter()
+        .filter(|glyph| !regions.iter().any(|region| region.contains(glyph)))
+        .cloned()
+        .collect();
+    regions.extend(aligned_tables(&rest));
+    regions.sort_by(|a, b| a.top.total_cmp(&b.top));
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::pdf::testing::{document, save};
+    use crate::extraction::{PdfExtractor, PdfOptions};
+    use pretty_assertions::assert_eq;
+
+    fn text(x: f64, y: f64, text: &str) -> String {
+        format!("BT /F1 10 Tf {} {} Td ({}) Tj ET\n", x, y, text)
+    }
+
+    fn extract(content: &str) -> (String, Vec<PdfTable>) {
+        let (mut document, _) = document(&[content]);
+        let options = PdfOptions {
+            extract_tables: true,
+            ..Default::default()
+        };
+        let document = PdfExtractor::extract_document(&save(&mut document), &options).unwrap();
+        let page = document.pages.into_iter().next().unwrap();
+        (page.text, page.tables)
+    }
+
+    #[test]
+    fn test_ruled_table() {
+        let mut content = text(72., 740., "Results are listed below.");
+        // a 3x3 grid, with the second row spanning the last two columns
+        for y in [700, 680, 660, 640] {
+            content.push_str(&format!("72 {} m 400 {} l S\n", y, y));
+        }
+        content.push_str("72 640 m 72 700 l S 400 640 m 400 700 l S 180 640 m 180 700 l S\n");
+        content.push_str("290 680 m 290 700 l S 290 640 m 290 660 l S\n");
+        content.push_str(&text(76., 686., "Sample"));
+        content.push_str(&text(184., 686., "Mass"));
+        content.push_str(&text(294., 686., "Notes, if any"));
+        content.push_str(&text(76., 666., "A"));
+        content.push_str(&text(184., 666., "Not measured"));
+        content.push_str(&text(76., 646., "B"));
+        content.push_str(&text(184., 646., "12"));
+        content.push_str(&text(294., 646., "Rerun"));
+        content.push_str(&text(72., 600., "The end."));
+
+        let (text, tables) = extract(&content);
+
+        assert_eq!(
+            tables,
+            vec![PdfTable {
+                rows: vec![
+                    vec!["Sample".into(), "Mass".into(), "Notes, if any".into()],
+                    vec!["A".into(), "Not measured".into(), "".into()],
+                    vec!["B".into(), "12".into(), "Rerun".into()],
+                ],
+            }]
+        );
+        assert_eq!(
+            tables[0].to_csv(),
+            "Sample,Mass,\"Notes, if any\"\nA,Not measured,\nB,12,Rerun\n"
+        );
+        assert_eq!(
+            text.trim(),
+            "Results are listed below.\n\n\
+             | Sample | Mass | Notes, if any |\n\
+             | --- | --- | --- |\n\
+             | A | Not measured |  |\n\
+             | B | 12 | Rerun |\n\n\
+             The end."
+        );
+    }
+
+    #[test]
+    fn test_aligned_table() {
+        let mut content = text(72., 740., "Quarterly figures follow.");
+        for (i, (name, value)) in [("Region", "Sales"), ("North", "120"), ("South", "95")]
+            .iter()
+            .enumerate()
+        {
+            let y = 700. - 14. * i as f64;
+            content.push_str(&text(72., y, name));
+            content.push_str(&text(300., y, value));
+        }
+
+        let (text, tables) = extract(&content);
+
+        assert_eq!(tables.len(), 1);
+        assert_eq!(
+            tables[0].to_markdown(),
+            "| Region | Sales |\n| --- | --- |\n| North | 120 |\n| South | 95 |\n"
+        );
+        assert!(text
+            .trim_start()
+            .starts_with("Quarterly figures follow.\n\n| Region | Sales |"));
+    }
+
+    #[test]
+    fn test_prose_is_not_a_table() {
+        let (_, tables) = extract(&format!(
+            "{}{}{}",
+            text(72., 700., "A paragraph of text that runs"),
+            text(72., 686., "over several lines, without"),
+            text(72., 672., "any columns at all."),
+        ));
+
+        assert!(tables.is_empty());
+    }
+
+    #[test]
+    fn test_tables_disabled() {
+        let content = format!("{}{}", text(72., 700., "North"), text(300., 700., "120"));
+        let (mut document, _) = document(&[&content]);
+        let document =
+            PdfExtractor::extract_document(&save(&mut document), &PdfOptions::default()).unwrap();
+
+        assert!(document.pages[0].tables.is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers to build small PDF documents in memory for tests.
+
+use lopdf::{dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+/// A document with one Letter-sized page per content stream, using Helvetica as `/F1`.
+pub(crate) fn document(contents: &[&str]) -> (Document, Vec<ObjectId>) {
+    let mut document = Document::with_version("1.7");
+    let pages_id = document.new_object_id();
+    let font_id = document.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+
+    let page_ids: Vec<ObjectId> = contents
+        .iter()
+        .map(|content| {
+            let content_id =
+                document.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+            document.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { "F1" => font_id },
+                },
+            })
+        })
+        .collect();
+
+    document.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => page_ids.iter().map(|id| Object::Reference(*id)).collect::<Vec<_>>(),
+            "Count" => page_ids.len() as i64,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        }),
+    );
+    let catalog_id = document.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    document.trailer.set("Root", catalog_id);
+    (document, page_ids)
+}
+
+/// The catalog dictionary of a document built with [`document`].
+pub(crate) fn catalog(document: &mut Document) -> &mut lopdf::Dictionary {
+    let id = document
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .unwrap();
+    document
+        .get_object_mut(id)
+        .and_then(Object::as_dict_mut)
+        .unwrap()
+}
+
+/// A literal string object.
+pub(crate) fn string(text: &str) -> Object {
+    Object::String(text.as_bytes().to_vec(), StringFormat::Literal)
+}
+
+pub(crate) fn save(document: &mut Document) -> Vec<u8> {
+    let mut data = Vec::new();
+    document.save_to(&mut data).unwrap();
+    data
+}
@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use xml::reader::{ParserConfig, XmlEvent};
+
+use super::Extract;
+
+pub struct XmlExtractor;
+pub struct JsonExtractor;
+
+///
+/// Options for the structured (XML and JSON) extractors.
+///
+/// Selectors use a subset of XPath for XML (`/a/b`, `//b`, `*`, `b[2]`, `@attr`) and of
+/// JSONPath for JSON (`$.a.b`, `$..b`, `[*]`, `[2]`, `['a b']`). When `include` is empty
+/// every text value is emitted; values under an `exclude` match are always dropped.
+#[derive(Debug, Clone, Default)]
+pub struct StructuredOptions {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub with_paths: bool,
+}
+
+/// A single text value together with its element path (XML) or JSON pointer (JSON).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextValue {
+    pub path: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    name: Option<String>,
+    index: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    descendant: bool,
+    name: Option<String>,
+    index: Option<usize>,
+}
+
+impl Step {
+    fn test(&self, segment: &Segment) -> bool {
+        let name_matches = match (&self.name, &segment.name) {
+            (None, _) => true,
+            (Some(expected), Some(actual)) => {
+                expected == actual
+                    || actual.rsplit_once(':').map(|(_, local)| local) == Some(expected.as_str())
+            }
+            (Some(_), None) => false,
+        };
+        name_matches && (self.index.is_none() || self.index == segment.index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Selector {
+    steps: Vec<Step>,
+    attribute: Option<String>,
+}
+
+impl Selector {
+    fn parse_xpath(expr: &str) -> Result<Selector> {
+        let expr = expr.trim();
+        if expr.is_empty() {
+            bail!("Empty XPath selector");
+        }
+
+        let mut steps = Vec::new();
+        let mut attribute = None;
+        let mut rest = expr;
+        let mut descendant = !expr.starts_with('/');
+
+        while !rest.is_empty() {
+            if let Some(stripped) = rest.strip_prefix("//") {
+                descendant = true;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix('/') {
+                rest = stripped;
+            }
+
+            let end = rest.find('/').unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = &rest[end..];
+
+            if token.is_empty() {
+                bail!("Invalid XPath selector '{}'", expr);
+            }
+            if attribute.is_some() {
+                bail!("Attribute step must be last in XPath selector '{}'", expr);
+            }
+
+            if let Some(name) = token.strip_prefix('@') {
+                attribute = Some(name.to_string());
+            } else if token == "text()" || token == "." {
+                if !rest.is_empty() {
+                    bail!("'{}' must be last in XPath selector '{}'", token, expr);
+                }
+            } else {
+                let (name, index) = match token.split_once('[') {
+                    Some((name, predicate)) => {
+                        let position = predicate
+                            .strip_suffix(']')
+                            .and_then(|p| p.trim().parse::<usize>().ok())
+                            .filter(|p| *p > 0)
+                            .with_context(|| {
+                                format!("Unsupported predicate in XPath selector '{}'", expr)
+                            })?;
+                        (name, Some(position))
+                    }
+                    None => (token, None),
+                };
+                steps.push(Step {
+                    descendant,
+                    name: (name != "*").then(|| name.to_string()),
+                    index,
+                });
+            }
+            descendant = false;
+        }
+
+        Ok(Selector { steps, attribute })
+    }
+
+    fn parse_jsonpath(expr: &str) -> Result<Selector> {
+        let expr = expr.trim();
+        let mut rest = match expr.strip_prefix('$') {
+            Some(rest) => rest,
+            None => bail!("JSONPath selector '{}' must start with '$'", expr),
+        };
+
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let mut descendant = false;
+            if let Some(stripped) = rest.strip_prefix("..") {
+                descendant = true;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix('.') {
+                rest = stripped;
+            }
+
+            if let Some(stripped) = rest.strip_prefix('[') {
+                let end = bracket_end(stripped)
+                    .with_context(|| format!("Unclosed '[' in JSONPath selector '{}'", expr))?;
+                let inner = stripped[..end].trim();
+                rest = &stripped[end + 1..];
+
+                let step = if inner == "*" {
+                    Step {
+                        descendant,
+                        name: None,
+                        index: None,
+                    }
+                } else if let Some(key) = unquote(inner) {
+                    Step {
+                        descendant,
+                        name: Some(key.to_string()),
+                        index: None,
+                    }
+                } else if let Ok(index) = inner.parse::<usize>() {
+                    Step {
+                        descendant,
+                        name: None,
+                        index: Some(index),
+                    }
+                } else {
+                    bail!(
+                        "Unsupported bracket expression in JSONPath selector '{}'",
+                        expr
+                    );
+                };
+                steps.push(step);
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let token = &rest[..end];
+                rest = &rest[end..];
+
+                if token.is_empty() {
+                    bail!("Invalid JSONPath selector '{}'", expr);
+                }
+                steps.push(Step {
+                    descendant,
+                    name: (token != "*").then(|| token.to_string()),
+                    index: None,
+                });
+            }
+        }
+
+        Ok(Selector {
+            steps,
+            attribute: None,
+        })
+    }
+
+    /// Whether the selector matches exactly the node at `path`.
+    fn matches(&self, path: &[Segment]) -> bool {
+        match_steps(&self.steps, path)
+    }
+
+    /// Whether the selector matches the node at `path` or one of its ancestors.
+    fn selects(&self, path: &[Segment]) -> bool {
+        (0..=path.len()).any(|len| self.matches(&path[..len]))
+    }
+}
+
+fn match_steps(steps: &[Step], path: &[Segment]) -> bool {
+    let Some((step, rest)) = steps.split_first() else {
+        return path.is_empty();
+    };
+
+    if step.descendant {
+        (0..path.len()).any(|i| step.test(&path[i]) && match_steps(rest, &path[i + 1..]))
+    } else {
+        !path.is_empty() && step.test(&path[0]) && match_steps(rest, &path[1..])
+    }
+}
+
+fn bracket_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, ']') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unquote(text: &str) -> Option<&str> {
+    text.strip_prefix('\'')
+        .and_then(|t| t.strip_suffix('\''))
+        .or_else(|| text.strip_prefix('"').and_then(|t| t.strip_suffix('"')))
+}
+
+/// Include/exclude filter shared by both extractors.
+struct Filter {
+    include: Vec<Selector>,
+    exclude: Vec<Selector>,
+}
+
+impl Filter {
+    fn new(options: &StructuredOptions, parse: fn(&str) -> Result<Selector>) -> Result<Filter> {
+        Ok(Filter {
+            include: options
+                .include
+                .iter()
+                .map(|s| parse(s))
+                .collect::<Result<_>>()?,
+            exclude: options
+                .exclude
+                .iter()
+                .map(|s| parse(s))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn allows_text(&self, path: &[Segment]) -> bool {
+        let excluded = self
+            .exclude
+            .iter()
+            .any(|s| s.attribute.is_none() && s.selects(path));
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|s| s.attribute.is_none() && s.selects(path));
+        included && !excluded
+    }
+
+    fn allows_attribute(&self, path: &[Segment], attribute: &str) -> bool {
+        let attribute_matches = |s: &Selector| match &s.attribute {
+            Some(name) => (name == "*" || name == attribute) && s.matches(path),
+            None => false,
+        };
+        let excluded = self
+            .exclude
+            .iter()
+            .any(|s| attribute_matches(s) || (s.attribute.is_none() && s.selects(path)));
+        self.include.iter().any(attribute_matches) && !excluded
+    }
+}
+
+fn xml_path(path: &[Segment], attribute: Option<&str>) -> String {
+    let mut result = String::new();
+    for (i, segment) in path.iter().enumerate() {
+        result.push('/');
+        result.push_str(segment.name.as_deref().unwrap_or("*"));
+        if i > 0 {
+            if let Some(index) = segment.index {
+                result.push_str(&format!("[{}]", index));
+            }
+        }
+    }
+    if let Some(attribute) = attribute {
+        result.push_str("/@");
+        result.push_str(attribute);
+    }
+    result
+}
+
+fn json_pointer(path: &[Segment]) -> String {
+    let mut result = String::new();
+    for segment in path {
+        result.push('/');
+        match (&segment.name, segment.index) {
+            (Some(key), _) => result.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            (None, Some(index)) => result.push_str(&index.to_string()),
+            (None, None) => {}
+        }
+    }
+    result
+}
+
+fn render(values: Vec<TextValue>, with_paths: bool) -> String {
+    values
+        .into_iter()
+        .map(|value| {
+            if with_paths {
+                format!("{}\t{}", value.path, value.text)
+            } else {
+                value.text
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl XmlExtractor {
+    ///
+    /// Extracts the text nodes (and any attributes explicitly selected with `@name`) of an
+    /// XML document, in document order, skipping all markup.
+    pub fn extract_values(data: &[u8], options: &StructuredOptions) -> Result<Vec<TextValue>> {
+        let filter = Filter::new(options, Selector::parse_xpath)?;
+        let parser = ParserConfig::new()
+            .trim_whitespace(true)
+            .cdata_to_characters(true)
+            .ignore_comments(true)
+            .create_reader(data);
+
+        let mut values = Vec::new();
+        let mut path: Vec<Segment> = Vec::new();
+        // per-element counters used to compute the position of each child among its siblings
+        let mut sibling_counts: Vec<HashMap<String, usize>> = vec![HashMap::new()];
+
+        for event in parser {
+            match event.context("Failed to parse XML")? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let qualified = match &name.prefix {
+                        Some(prefix) => format!("{}:{}", prefix, name.local_name),
+                        None => name.local_name.clone(),
+                    };
+                    let counts = sibling_counts
+                        .last_mut()
+                        .expect("counter stack is never empty");
+                    let position = counts.entry(qualified.clone()).or_insert(0);
+                    *position += 1;
+
+                    path.push(Segment {
+                        name: Some(qualified),
+                        index: Some(*position),
+                    });
+                    sibling_counts.push(HashMap::new());
+
+                    for attribute in attributes {
+                        let attribute_name = match &attribute.name.prefix {
+                            Some(prefix) => format!("{}:{}", prefix, attribute.name.local_name),
+                            None => attribute.name.local_name.clone(),
+                        };
+                        if filter.allows_attribute(&path, &attribute_name)
+                            && !attribute.value.trim().is_empty()
+                        {
+                            values.push(TextValue {
+                                path: xml_path(&path, Some(&attribute_name)),
+                                text: attribute.value.trim().to_string(),
+                            });
+                        }
+                    }
+                }
+                XmlEvent::EndElement { .. } => {
+                    path.pop();
+                    sibling_counts.pop();
+                }
+                XmlEvent::Characters(text) if filter.allows_text(&path) => {
+                    values.push(TextValue {
+                        path: xml_path(&path, None),
+                        text,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(values)
+    }
+
+    pub fn extract_with_options(data: &[u8], options: &StructuredOptions) -> Result<String> {
+        let values = Self::extract_values(data, options)?;
+        Ok(render(values, options.with_paths))
+    }
+}
+
+impl Extract for XmlExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        Self::extract_with_options(data, &StructuredOptions::default())
+    }
+}
+
+impl JsonExtractor {
+    ///
+    /// Extracts every string value of a JSON document in document order. Object keys,
+    /// numbers, booleans and nulls are skipped.
+    pub fn extract_values(data: &[u8], options: &StructuredOptions) -> Result<Vec<TextValue>> {
+        let filter = Filter::new(options, Selector::parse_jsonpath)?;
+        let root: Value = serde_json::from_slice(data).context("Failed to parse JSON")?;
+
+        let mut values = Vec::new();
+        let mut path = Vec::new();
+        collect_json(&root, &mut path, &filter, &mut values);
+        Ok(values)
+    }
+
+    pub fn extract_with_options(data: &[u8], options: &StructuredOptions) -> Result<String> {
+        let values = Self::extract_values(data, options)?;
+        Ok(render(values, options.with_paths))
+    }
+}
+
+impl Extract for JsonExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        Self::extract_with_options(data, &StructuredOptions::default())
+    }
+}
+
+fn collect_json(
+    value: &Value,
+    path: &mut Vec<Segment>,
+    filter: &Filter,
+    values: &mut Vec<TextValue>,
+) {
+    match value {
+        Value::String(text) if !text.trim().is_empty() && filter.allows_text(path) => {
+            values.push(TextValue {
+                path: json_pointer(path),
+                text: text.clone(),
+            });
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(Segment {
+                    name: None,
+                    index: Some(index),
+                });
+                collect_json(item, path, filter, values);
+                path.pop();
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                path.push(Segment {
+                    name: Some(key.clone()),
+                    index: None,
+                });
+                collect_json(item, path, filter, values);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CATALOG: &[u8] = include_bytes!("../../tests/data/catalog.xml");
+    const ORDERS: &[u8] = include_bytes!("../../tests/data/orders.json");
+
+    fn options(include: &[&str], exclude: &[&str]) -> StructuredOptions {
+        StructuredOptions {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            with_paths: true,
+        }
+    }
+
+    #[test]
+    fn test_xml_text_only() {
+        let text = XmlExtractor::extract(CATALOG).unwrap();
+
+        assert!(text.contains("Gambardella, Matthew"));
+        assert!(!text.contains('<'));
+        assert!(!text.contains("bk101"));
+    }
+
+    #[test]
+    fn test_xml_selectors() {
+        let values = XmlExtractor::extract_values(
+            CATALOG,
+            &options(&["//book[2]/title", "/catalog/book/@id"], &[]),
+        )
+        .unwrap();
+        let values: Vec<_> = values
+            .iter()
+            .map(|v| (v.path.as_str(), v.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                ("/catalog/book[1]/@id", "bk101"),
+                ("/catalog/book[2]/@id", "bk102"),
+                ("/catalog/book[2]/title[1]", "Midnight Rain"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xml_exclude() {
+        let text =
+            XmlExtractor::extract_with_options(CATALOG, &options(&[], &["description", "price"]))
+                .unwrap();
+
+        assert!(text.contains("XML Developer's Guide"));
+        assert!(!text.contains("An in-depth look"));
+        assert!(!text.contains("44.95"));
+    }
+
+    #[test]
+    fn test_json_strings_with_pointers() {
+        let values =
+            JsonExtractor::extract_values(ORDERS, &options(&["$.orders[*].items..name"], &[]))
+                .unwrap();
+        let values: Vec<_> = values
+            .iter()
+            .map(|v| (v.path.as_str(), v.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                ("/orders/0/items/0/name", "Widget"),
+                ("/orders/0/items/1/name", "Gadget"),
+                ("/orders/1/items/0/name", "Sprocket"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_exclude() {
+        let text = JsonExtractor::extract_with_options(
+            ORDERS,
+            &StructuredOptions {
+                exclude: vec!["$..sku".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(text.contains("Ada Lovelace"));
+        assert!(!text.contains("W-1"));
+        assert!(!text.contains("19.99"));
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        assert!(Selector::parse_jsonpath("orders").is_err());
+        assert!(Selector::parse_xpath("/a/@b/c").is_err());
+        assert!(Selector::parse_xpath("/a[last()]").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{bail, Result};
+
+use super::content_line;
+use super::Extract;
+
+pub struct VcfExtractor;
+
+/// A single contact (`BEGIN:VCARD` ... `END:VCARD`) from a vCard file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Contact {
+    pub name: Option<String>,
+    pub organization: Option<String>,
+    pub title: Option<String>,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+impl Contact {
+    fn to_text(&self) -> String {
+        let mut lines = vec![format!(
+            "Contact: {}",
+            self.name.as_deref().unwrap_or("(unnamed)")
+        )];
+        if let Some(organization) = &self.organization {
+            lines.push(format!("Organization: {}", organization));
+        }
+        if let Some(title) = &self.title {
+            lines.push(format!("Title: {}", title));
+        }
+        for email in &self.emails {
+            lines.push(format!("Email: {}", email));
+        }
+        for phone in &self.phones {
+            lines.push(format!("Phone: {}", phone));
+        }
+        for note in &self.notes {
+            lines.push(format!("Note: {}", note));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Builds a display name from the structured `N` property (family;given;additional;prefix;suffix).
+fn structured_name(components: &[String]) -> Option<String> {
+    let part = |i: usize| components.get(i).map(|s| s.trim()).unwrap_or("");
+    let ordered = [part(3), part(1), part(2), part(0), part(4)];
+    let name = ordered
+        .iter()
+        .filter(|s| !s.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+impl VcfExtractor {
+    /// Parses every contact in a vCard file (versions 2.1, 3.0 and 4.0).
+    pub fn contacts(data: &[u8]) -> Result<Vec<Contact>> {
+        let text = String::from_utf8_lossy(data);
+        let properties = content_line::parse(&text);
+        if !properties
+            .first()
+            .is_some_and(|p| p.name == "BEGIN" && p.value.trim().eq_ignore_ascii_case("VCARD"))
+        {
+            bail!("Not a vCard file: missing BEGIN:VCARD");
+        }
+
+        let mut contacts = Vec::new();
+        let mut current: Option<Contact> = None;
+        let mut structured: Option<String> = None;
+
+        for property in &properties {
+            match property.name.as_str() {
+                "BEGIN" if property.value.trim().eq_ignore_ascii_case("VCARD") => {
+                    current = Some(Contact::default());
+                    structured = None;
+                }
+                "END" if property.value.trim().eq_ignore_ascii_case("VCARD") => {
+                    if let Some(mut contact) = current.take() {
+                        if contact.name.is_none() {
+                            contact.name = structured.take();
+                        }
+                        contacts.push(contact);
+                    }
+                }
+                name => {
+                    let Some(contact) = current.as_mut() else {
+                        continue;
+                    };
+                    let value = property.text().trim().to_string();
+                    match name {
+                        "FN" if !value.is_empty() => contact.name = Some(value),
+                        "N" => structured = structured_name(&property.components()),
+                        "ORG" => {
+                            let units: Vec<String> = property
+                                .components()
+                                .into_iter()
+                                .map(|c| c.trim().to_string())
+                                .filter(|c| !c.is_empty())
+                                .collect();
+                            if !units.is_empty() {
+                                contact.organization = Some(units.join(", "));
+                            }
+                        }
+                        "TITLE" if !value.is_empty() => contact.title = Some(value),
+                        "EMAIL" if !value.is_empty() => contact.emails.push(value),
+                        "TEL" if !value.is_empty() => {
+                            let value = value.strip_prefix("tel:").unwrap_or(&value).to_string();
+                            contact.phones.push(value)
+                        }
+                        "NOTE" if !value.is_empty() => contact.notes.push(value),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(contacts)
+    }
+}
+
+impl Extract for VcfExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        let contacts = Self::contacts(data)?;
+        Ok(contacts
+            .iter()
+            .map(Contact::to_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CONTACTS: &[u8] = include_bytes!("../../tests/data/contacts.vcf");
+
+    #[test]
+    fn test_parse_contacts() {
+        let contacts = VcfExtractor::contacts(CONTACTS).unwrap();
+
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].name.as_deref(), Some("Dr. Ada Lovelace"));
+        assert_eq!(
+            contacts[0].organization.as_deref(),
+            Some("Analytical Engines Ltd, Research")
+        );
+        assert_eq!(
+            contacts[0].emails,
+            vec!["ada@example.com", "ada.home@example.org"]
+        );
+        // vCard 2.1 card without FN, with a quoted-printable note
+        assert_eq!(contacts[1].name.as_deref(), Some("Jörg Müller"));
+        assert_eq!(
+            contacts[1].notes,
+            vec!["Met at the conference in Zürich,\nfollow up in May"]
+        );
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let text = VcfExtractor::extract(CONTACTS).unwrap();
+
+        assert!(text.starts_with(
+            "Contact: Dr. Ada Lovelace\nOrganization: Analytical Engines Ltd, Research"
+        ));
+        assert!(text.contains("\n\nContact: Jörg Müller\n"));
+        assert!(text.contains("Phone: +44 20 7946 0000"));
+    }
+}
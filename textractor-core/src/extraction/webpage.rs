@@ -0,0 +1,396 @@
+use std::io::Cursor;
+
+use anyhow::{bail, Context, Result};
+
+use super::mime::{decode_base64, decode_charset, decode_quoted_printable};
+use super::{Extract, HtmlExtractor};
+use crate::detection::find;
+
+pub struct MhtmlExtractor;
+pub struct WebArchiveExtractor;
+
+// multipart entities nested deeper than this are ignored
+const MAX_MULTIPART_DEPTH: usize = 32;
+
+/// Options for saved web page formats (MHTML and Safari `.webarchive`).
+#[derive(Debug, Clone, Default)]
+pub struct WebPageOptions {
+    /// Also extract the text of frames/iframes saved alongside the main page.
+    pub include_frames: bool,
+}
+
+struct MimePart<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+impl MimePart<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The lowercased media type and the parameters of the Content-Type header.
+    fn content_type(&self) -> (String, Vec<(String, String)>) {
+        let value = self.header("Content-Type").unwrap_or("text/plain");
+        let mut fields = split_params(value).into_iter();
+        let media_type = fields
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let params = fields
+            .filter_map(|field| {
+                let (key, value) = field.split_once('=')?;
+                Some((
+                    key.trim().to_ascii_lowercase(),
+                    value.trim().trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        (media_type, params)
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        self.content_type()
+            .1
+            .into_iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    fn is_html(&self) -> bool {
+        let (media_type, _) = self.content_type();
+        media_type == "text/html" || media_type == "application/xhtml+xml"
+    }
+
+    /// The body with its Content-Transfer-Encoding undone and converted from its charset.
+    fn decoded_text(&self) -> String {
+        let encoding = self
+            .header("Content-Transfer-Encoding")
+            .unwrap_or("7bit")
+            .trim()
+            .to_ascii_lowercase();
+        let bytes = match encoding.as_str() {
+            "base64" => decode_base64(self.body).unwrap_or_default(),
+            "quoted-printable" => decode_quoted_printable(self.body),
+            _ => self.body.to_vec(),
+        };
+        decode_charset(&bytes, self.param("charset").as_deref())
+    }
+}
+
+fn split_params(value: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                fields.last_mut().unwrap().push(c);
+            }
+            ';' if !in_quotes => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Splits a MIME entity into its (unfolded) headers and its body.
+fn parse_entity(data: &[u8]) -> MimePart<'_> {
+    let (head, body) = match find(data, b"\r\n\r\n") {
+        Some(end) => (&data[..end], &data[end + 4..]),
+        None => match find(data, b"\n\n") {
+            Some(end) => (&data[..end], &data[end + 2..]),
+            None => (data, &data[data.len()..]),
+        },
+    };
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in String::from_utf8_lossy(head).lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    MimePart { headers, body }
+}
+
+/// Flattens a (possibly nested) multipart entity into its leaf parts, in order.
+fn leaf_parts<'a>(entity: MimePart<'a>, depth: usize, parts: &mut Vec<MimePart<'a>>) {
+    let (media_type, _) = entity.content_type();
+    let boundary = match entity.param("boundary") {
+        Some(_) if media_type.starts_with("multipart/") && depth >= MAX_MULTIPART_DEPTH => return,
+        Some(boundary) if media_type.starts_with("multipart/") => boundary,
+        _ => {
+            parts.push(entity);
+            return;
+        }
+    };
+
+    let delimiter = format!("--{}", boundary);
+    let body = entity.body;
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    while let Some(pos) = find(&body[offset..], delimiter.as_bytes()) {
+        let start = offset + pos;
+        if start == 0 || body[start - 1] == b'\n' {
+            starts.push(start);
+        }
+        offset = start + delimiter.len();
+    }
+
+    for (i, start) in starts.iter().enumerate() {
+        let content = &body[start + delimiter.len()..];
+        if content.starts_with(b"--") {
+            break;
+        }
+        let end = starts.get(i + 1).map(|next| next - start - delimiter.len());
+        let content = match end {
+            Some(end) => &content[..end],
+            None => content,
+        };
+        // skip the remainder of the delimiter line and the line break preceding the next one
+        let content = match content.iter().position(|b| *b == b'\n') {
+            Some(newline) => &content[newline + 1..],
+            None => continue,
+        };
+        let content = content
+            .strip_suffix(b"\r\n")
+            .or_else(|| content.strip_suffix(b"\n"))
+            .unwrap_or(content);
+
+        leaf_parts(parse_entity(content), depth + 1, parts);
+    }
+}
+
+fn join_pages(pages: Vec<String>) -> String {
+    pages
+        .into_iter()
+        .map(|page| page.trim().to_string())
+        .filter(|page| !page.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+impl MhtmlExtractor {
+    ///
+    /// Extracts the text of the main HTML resource of an MHTML file. The root resource is the
+    /// part named by the `start` parameter if present, otherwise the first HTML part.
+    pub fn extract_with_options(data: &[u8], options: &WebPageOptions) -> Result<String> {
+        let root = parse_entity(data);
+        let start = root
+            .param("start")
+            .map(|cid| cid.trim_matches(|c| c == '<' || c == '>').to_string());
+
+        let mut parts = Vec::new();
+        leaf_parts(root, 0, &mut parts);
+
+        let main = start
+            .and_then(|cid| {
+                parts.iter().position(|part| {
+                    part.header("Content-ID")
+                        .map(|id| id.trim_matches(|c| c == '<' || c == '>'))
+                        == Some(cid.as_str())
+                })
+            })
+            .or_else(|| parts.iter().position(MimePart::is_html))
+            .context("MHTML file does not contain an HTML resource")?;
+
+        let mut pages = vec![HtmlExtractor::extract(
+            parts[main].decoded_text().as_bytes(),
+        )?];
+        if options.include_frames {
+            for (i, part) in parts.iter().enumerate() {
+                if i != main && part.is_html() {
+                    pages.push(HtmlExtractor::extract(part.decoded_text().as_bytes())?);
+                }
+            }
+        }
+
+        Ok(join_pages(pages))
+    }
+}
+
+impl Extract for MhtmlExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        Self::extract_with_options(data, &WebPageOptions::default())
+    }
+}
+
+/// Extracts the text of the `WebMainResource` of a (sub)archive dictionary.
+fn web_resource_text(archive: &plist::Dictionary) -> Result<Option<String>> {
+    let Some(resource) = archive
+        .get("WebMainResource")
+        .and_then(plist::Value::as_dictionary)
+    else {
+        return Ok(None);
+    };
+    let data = resource
+        .get("WebResourceData")
+        .and_then(plist::Value::as_data)
+        .unwrap_or_default();
+    let encoding = resource
+        .get("WebResourceTextEncodingName")
+        .and_then(plist::Value::as_string);
+    let mime_type = resource
+        .get("WebResourceMIMEType")
+        .and_then(plist::Value::as_string)
+        .unwrap_or("text/html");
+
+    let text = decode_charset(data, encoding);
+    if mime_type.starts_with("text/html") || mime_type.contains("xhtml") {
+        Ok(Some(HtmlExtractor::extract(text.as_bytes())?))
+    } else if mime_type.starts_with("text/") {
+        Ok(Some(text))
+    } else {
+        Ok(None)
+    }
+}
+
+fn collect_frames(archive: &plist::Dictionary, pages: &mut Vec<String>) -> Result<()> {
+    let frames = archive
+        .get("WebSubframeArchives")
+        .and_then(plist::Value::as_array);
+    for frame in frames.into_iter().flatten() {
+        if let Some(frame) = frame.as_dictionary() {
+            pages.extend(web_resource_text(frame)?);
+            collect_frames(frame, pages)?;
+        }
+    }
+    Ok(())
+}
+
+impl WebArchiveExtractor {
+    /// Extracts the text of the main resource of a Safari `.webarchive` (binary or XML plist).
+    pub fn extract_with_options(data: &[u8], options: &WebPageOptions) -> Result<String> {
+        let value = plist::Value::from_reader(Cursor::new(data)).context("Failed to read plist")?;
+        let Some(archive) = value.as_dictionary() else {
+            bail!("Web archive root is not a dictionary");
+        };
+
+        let mut pages = match web_resource_text(archive)? {
+            Some(text) => vec![text],
+            None => bail!("Web archive does not contain a main resource"),
+        };
+        if options.include_frames {
+            collect_frames(archive, &mut pages)?;
+        }
+
+        Ok(join_pages(pages))
+    }
+}
+
+impl Extract for WebArchiveExtractor {
+    fn extract(data: &[u8]) -> Result<String, anyhow::Error> {
+        Self::extract_with_options(data, &WebPageOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::ContentType;
+    use pretty_assertions::assert_eq;
+
+    const PAGE: &[u8] = include_bytes!("../../tests/data/page.mhtml");
+
+    fn resource(html: &str, encoding: &str) -> plist::Value {
+        let mut resource = plist::Dictionary::new();
+        resource.insert(
+            "WebResourceData".into(),
+            plist::Value::Data(html.as_bytes().to_vec()),
+        );
+        resource.insert("WebResourceMIMEType".into(), "text/html".into());
+        resource.insert("WebResourceTextEncodingName".into(), encoding.into());
+        resource.insert("WebResourceURL".into(), "https://example.com/".into());
+        plist::Value::Dictionary(resource)
+    }
+
+    #[test]
+    fn test_extract_mhtml() {
+        assert_eq!(ContentType::from(PAGE), ContentType::Mhtml);
+
+        let text = MhtmlExtractor::extract(PAGE).unwrap();
+
+        assert!(text.contains("Saved pages are searchable — finally."));
+        assert!(!text.contains("=E2=80=94"));
+        assert!(!text.contains("Embedded frame"));
+    }
+
+    #[test]
+    fn test_extract_mhtml_frames() {
+        let options = WebPageOptions {
+            include_frames: true,
+        };
+        let text = MhtmlExtractor::extract_with_options(PAGE, &options).unwrap();
+
+        assert!(text.ends_with("Embedded frame content"));
+    }
+
+    #[test]
+    fn test_nested_multipart_limit() {
+        let mut nested = "Content-Type: text/html\r\n\r\n<p>Deep</p>".to_string();
+        for level in 0..10_000 {
+            nested = format!(
+                "Content-Type: multipart/related; boundary=\"b{level}\"\r\n\r\n--b{level}\r\n{nested}\r\n--b{level}--\r\n"
+            );
+        }
+        let data = format!(
+            "MIME-Version: 1.0\r\nContent-Type: multipart/related; boundary=\"top\"\r\n\r\n\
+             --top\r\nContent-Type: text/html\r\n\r\n<p>Shallow</p>\r\n--top\r\n{nested}\r\n--top--\r\n"
+        );
+
+        let text = MhtmlExtractor::extract(data.as_bytes()).unwrap();
+
+        assert_eq!(text, "Shallow");
+    }
+
+    #[test]
+    fn test_extract_webarchive() {
+        let mut frame = plist::Dictionary::new();
+        frame.insert(
+            "WebMainResource".into(),
+            resource("<body><p>Frame text</p></body>", "UTF-8"),
+        );
+
+        let mut archive = plist::Dictionary::new();
+        archive.insert(
+            "WebMainResource".into(),
+            resource(
+                "<html><body><h1>Caf\u{e9}</h1><p>Main text</p></body></html>",
+                "UTF-8",
+            ),
+        );
+        archive.insert(
+            "WebSubframeArchives".into(),
+            plist::Value::Array(vec![plist::Value::Dictionary(frame)]),
+        );
+        let mut data = Vec::new();
+        plist::Value::Dictionary(archive)
+            .to_writer_binary(&mut data)
+            .unwrap();
+
+        assert_eq!(ContentType::from(data.as_slice()), ContentType::WebArchive);
+        assert_eq!(
+            WebArchiveExtractor::extract(&data).unwrap(),
+            "CaféMain text"
+        );
+
+        let options = WebPageOptions {
+            include_frames: true,
+        };
+        assert_eq!(
+            WebArchiveExtractor::extract_with_options(&data, &options).unwrap(),
+            "CaféMain text\n\nFrame text"
+        );
+    }
+}
@@ -1,2 +1,3 @@
 pub mod detection;
+pub mod errors;
 pub mod extraction;
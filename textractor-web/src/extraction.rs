@@ -1,4 +1,6 @@
 use axum::body::Bytes;
+use textractor::detection::ContentType;
+use textractor::extraction::{PdfCleanup, PdfExtractor, PdfOptions};
 
 use crate::models::ExtractionResult;
 
@@ -9,11 +11,39 @@ pub async fn process_extraction(
     content_type: String,
 ) -> Result<ExtractionResult, String> {
     let start = std::time::Instant::now();
-    let text =
-        textractor::extraction::extract(data).map_err(|e| format!("Extraction failed: {}", e))?;
+    // PDFs are extracted page by page, so that pages without a text layer can be reported
+    let (text, image_only_pages) = match ContentType::from(data.as_ref()) {
+        ContentType::Pdf => {
+            let options = PdfOptions {
+                include_attachments: true,
+                cleanup: PdfCleanup::all(),
+                ..Default::default()
+            };
+            let document = PdfExtractor::extract_document(data, &options)
+                .map_err(|e| format!("Extraction failed: {}", e))?;
+            (Some(document.text()), document.image_only_pages())
+        }
+        _ => {
+            let text = textractor::extraction::extract(data)
+                .map_err(|e| format!("Extraction failed: {}", e))?;
+            (text, Vec::new())
+        }
+    };
     let elapsed = start.elapsed();
 
     let result = match text {
+        Some(text) if !image_only_pages.is_empty() && text.trim().is_empty() => ExtractionResult {
+            extraction_time: elapsed.as_secs_f32(),
+            success: false,
+            name,
+            file_name,
+            content_type,
+            text: None,
+            image_only_pages,
+            error: Some(
+                "The document has no text layer (scanned pages), OCR is required".to_string(),
+            ),
+        },
         Some(text) => ExtractionResult {
             extraction_time: elapsed.as_secs_f32(),
             success: true,
@@ -21,6 +51,7 @@ pub async fn process_extraction(
             file_name,
             content_type,
             text: Some(text),
+            image_only_pages,
             error: None,
         },
         None => ExtractionResult {
@@ -30,6 +61,7 @@ pub async fn process_extraction(
             file_name,
             content_type,
             text: None,
+            image_only_pages,
             error: Some("Unsupported file type".to_string()),
         },
     };
@@ -16,6 +16,8 @@ pub struct ExtractionResult {
     pub file_name: String,
     pub content_type: String,
     pub text: Option<String>,
+    /// Pages of a PDF that have no text layer (e.g. scans), and need OCR to be extracted.
+    pub image_only_pages: Vec<u32>,
     pub error: Option<String>,
 }
 
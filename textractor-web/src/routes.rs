@@ -62,6 +62,7 @@ pub async fn extract(mut multipart: Multipart) -> Result<Json<ExtractionResponse
                             file_name,
                             content_type,
                             text: None,
+                            image_only_pages: Vec::new(),
                             error: Some(err),
                         });
                     }
@@ -76,6 +77,7 @@ pub async fn extract(mut multipart: Multipart) -> Result<Json<ExtractionResponse
                     file_name,
                     content_type,
                     text: None,
+                    image_only_pages: Vec::new(),
                     error: Some(format!("Failed to read file bytes: {}", err)),
                 });
             }